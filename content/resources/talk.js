@@ -15,12 +15,37 @@ document.body.addEventListener(
 				event.preventDefault();
 				goToNext();
 				break;
+			case "p":
+				if (!isPresenterView) {
+					event.preventDefault();
+					window.open(location.pathname.replace(/\.html$/, ".notes.html") + location.hash, "notes");
+				}
+				break;
 		}
 	},
 	{ passive: false },
 );
 
-window.addEventListener("click", goToNext);
+const isPresenterView = location.pathname.endsWith(".notes.html");
+
+// Clicking around the presenter view shouldn't change the slide
+if (!isPresenterView) {
+	window.addEventListener("click", goToNext);
+}
+
+// Keep the slides and the presenter view on the same slide, even in separate windows
+const channel = new BroadcastChannel(location.pathname.replace(/\.notes\.html$/, ".html"));
+window.addEventListener("hashchange", () => {
+	// Moving between slides briefly clears the hash, which isn't worth following
+	if (location.hash) {
+		channel.postMessage(getCurrentSlideIndex());
+	}
+});
+channel.addEventListener("message", (event) => {
+	if (event.data !== getCurrentSlideIndex()) {
+		location.hash = statePrefix + event.data;
+	}
+});
 
 const statePrefix = "slide-";
 function getCurrentSlideIndex() {
@@ -1,18 +1,28 @@
 use chrono::NaiveDate;
+use chrono::NaiveTime;
 use handlebars::Handlebars;
 use serde::ser::SerializeStruct;
 use serde::Deserialize;
 use serde::Serialize;
 use serde::Serializer;
+use serde_json::json;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 use url::Url;
 
+use crate::config;
 use crate::config::BLOG;
+use crate::index_entry::SortKey;
+use crate::meta::Meta;
 use crate::pocky::de;
+use crate::pocky::md;
+use crate::pocky::md::Excerpt;
+use crate::pocky::md::TocEntry;
 use crate::pocky::AsHtml;
+use crate::pocky::BuildError;
+use crate::pocky::FromFile;
 use crate::pocky::MarkdownPage;
 
 #[derive(Clone, Debug, Serialize, Eq, PartialEq)]
@@ -22,6 +32,15 @@ pub struct BlogPost {
 	#[serde(flatten)]
 	pub metadata: BlogPostMetadata,
 	pub content: String,
+	/// The markdown that `content` was rendered from
+	#[serde(skip)]
+	pub source: String,
+	/// The headings of the post, if the post asked for a table of contents
+	pub toc: Vec<TocEntry>,
+	/// The `summary` if there is one, or otherwise the start of the post
+	pub excerpt: Option<Excerpt>,
+	#[serde(skip)]
+	pub warnings: Vec<BuildError>,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
@@ -34,14 +53,38 @@ pub struct BlogPostMetadata {
 	pub status: BlogPostStatus,
 	#[serde(default, deserialize_with = "de::date_option")]
 	pub date: Option<NaiveDate>,
+	#[serde(default, deserialize_with = "de::time_option", skip_serializing)]
+	pub time: Option<NaiveTime>,
+	/// Orders entries that share a date and time, lowest first
+	#[serde(default)]
+	pub weight: i32,
 	#[serde(default, deserialize_with = "de::markdown_option")]
 	pub summary: Option<String>,
 	#[serde(default, deserialize_with = "de::comma_separated")]
 	pub tags: Vec<String>,
+	/// Old paths of the post, like `/posts/gleam-traits.html`, which redirect to it
+	#[serde(default, deserialize_with = "de::comma_separated")]
+	pub aliases: Vec<String>,
 	pub accent_color: Option<String>,
 	pub cover: Option<HashMap<String, String>>,
 	pub hn: Option<String>,
 	pub lobsters: Option<String>,
+	/// Whether to show a table of contents at the top of the post
+	#[serde(default)]
+	pub toc: bool,
+	/// Counted from the markdown source, rather than set in the frontmatter
+	#[serde(skip_deserializing)]
+	pub word_count: usize,
+}
+
+/// How many words we expect someone to read in a minute
+const WORDS_PER_MINUTE: usize = 200;
+
+impl BlogPostMetadata {
+	/// How long it takes to read the post, in minutes, rounded up
+	pub fn reading_time(&self) -> usize {
+		self.word_count.div_ceil(WORDS_PER_MINUTE).max(1)
+	}
 }
 
 // Custom `Serialize` implementation so that we can have two formats of `date`
@@ -53,7 +96,7 @@ impl Serialize for BlogPostMetadata {
 		let ser_date =
 			|date: &Option<NaiveDate>, format: &str| date.map(|date| date.format(format).to_string());
 
-		let mut state = ser.serialize_struct("BlogPostMetadata", 10)?;
+		let mut state = ser.serialize_struct("BlogPostMetadata", 13)?;
 		state.serialize_field("title", &self.title)?;
 		state.serialize_field("author", &self.author)?;
 		state.serialize_field("date", &ser_date(&self.date, "%A, %B %-d, %Y"))?;
@@ -65,6 +108,8 @@ impl Serialize for BlogPostMetadata {
 		state.serialize_field("status", &self.status)?;
 		state.serialize_field("hn", &self.hn)?;
 		state.serialize_field("lobsters", &self.lobsters)?;
+		state.serialize_field("word_count", &self.word_count)?;
+		state.serialize_field("reading_time", &self.reading_time())?;
 		state.end()
 	}
 }
@@ -79,58 +124,86 @@ pub enum BlogPostStatus {
 	Unlisted,
 }
 
-impl<P> From<P> for BlogPost
-where
-	P: AsRef<Path>,
-{
-	fn from(path: P) -> Self {
-		let page = MarkdownPage::<BlogPostMetadata>::from(&path);
+impl FromFile for BlogPost {
+	fn from_file(path: &Path) -> Result<Self, BuildError> {
+		let page = MarkdownPage::<BlogPostMetadata>::from_file(path)?;
+
+		let mut metadata = page.metadata.ok_or_else(|| {
+			BuildError::new("missing blog post metadata")
+				.in_file(path)
+				.with_hint("posts start with a `---` block that sets at least a `title` and an `author`")
+		})?;
 
-		let mut path = path.as_ref().to_owned();
+		let mut path = path.to_owned();
 		path.set_extension("html");
 
-		let metadata = page
-			.metadata
-			.expect(&format!("missing blog post metadata in {}", path.display()));
+		metadata.word_count = page.word_count;
+		let toc = match metadata.toc {
+			true => page.toc,
+			false => Vec::new(),
+		};
+
+		let excerpt = match &metadata.summary {
+			Some(summary) => Some(Excerpt::from_html(summary)),
+			None => page.excerpt,
+		};
 
-		BlogPost {
-			canonical_url: BlogPost::canonicalize_path(&path),
+		Ok(BlogPost {
+			canonical_url: config::canonicalize(&path),
 			path,
 			metadata,
 			content: page.content,
-		}
+			source: page.source,
+			toc,
+			excerpt,
+			warnings: page.warnings,
+		})
 	}
 }
 
 impl PartialOrd for BlogPost {
 	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-		Some(self.metadata.date.cmp(&other.metadata.date).reverse())
+		Some(self.cmp(other))
 	}
 }
 impl Ord for BlogPost {
 	fn cmp(&self, other: &Self) -> Ordering {
-		self.metadata.date.cmp(&other.metadata.date).reverse()
+		self.sort_key().cmp(&other.sort_key())
 	}
 }
 
 impl AsHtml for BlogPost {
 	fn as_html(&self) -> String {
-		let renderer = Handlebars::new();
+		let mut renderer = Handlebars::new();
 		renderer
-			.render_template(include_str!("./templates/blog_post.html"), self)
+			.register_partial("toc", include_str!("./templates/toc.html"))
+			.expect("failed to register handlebars partial");
+		let mut data = serde_json::to_value(self).expect("failed to serialize post");
+		data["meta"] = Meta::post(self).to_html().into();
+		data["blog"] = json!(&*BLOG);
+		renderer
+			.render_template(include_str!("./templates/blog_post.html"), &data)
 			.expect("failed to render handlebars")
 	}
 }
 
 impl BlogPost {
-	fn canonicalize_path(path: &Path) -> Url {
-		BLOG
-			.canonical_origin
-			.join(path.to_str().expect("path contains invalid characters"))
-			.expect("failed to create canonical_url")
+	pub fn sort_key(&self) -> SortKey<'_> {
+		SortKey::new(
+			self.metadata.date.as_ref(),
+			self.metadata.time.as_ref(),
+			self.metadata.weight,
+			&self.metadata.title,
+			&self.path,
+		)
 	}
 
 	pub fn canonicalize(&mut self) {
-		self.canonical_url = Self::canonicalize_path(&self.path)
+		self.canonical_url = config::canonicalize(&self.path)
+	}
+
+	/// The post rendered again, with every link made absolute
+	pub fn absolute_content(&self) -> String {
+		md::render(&self.source, true, Some(&self.canonical_url)).html
 	}
 }
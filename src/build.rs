@@ -0,0 +1,1072 @@
+use chrono::Local;
+use chrono::NaiveDate;
+use chrono::Utc;
+use handlebars::Handlebars;
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::blog_post::BlogPost;
+use crate::blog_post::BlogPostStatus::{Published, Unlisted};
+use crate::calendar::Calendar;
+use crate::calendar::CalendarEvent;
+use crate::config;
+use crate::config::BLOG;
+use crate::config::EXTERNAL_LINKS;
+use crate::config::REDIRECTS;
+use crate::feed::Feed;
+use crate::feed::FeedEntry;
+use crate::index_entry::IndexEntry;
+use crate::meta::Meta;
+use crate::options::Options;
+use crate::pocky::md;
+use crate::pocky::pages_from_directory;
+use crate::pocky::AsHtml;
+use crate::pocky::BuildError;
+use crate::pocky::Diagnostics;
+use crate::pocky::FromFile;
+use crate::preview::has_preview;
+use crate::preview::preview_path;
+use crate::preview::render_preview;
+use crate::redirect_page::RedirectPage;
+use crate::search::SearchIndex;
+use crate::search::STOP_WORDS;
+use crate::sitemap::robots_txt;
+use crate::sitemap::Sitemap;
+use crate::tags::tags;
+use crate::talk::Talk;
+
+/// Where rendered pages end up. A dry run renders everything, but doesn't write any
+/// of it to disk, which is what `check` uses.
+#[derive(Clone, Debug)]
+pub struct Output {
+	pub root: PathBuf,
+	pub dry_run: bool,
+}
+
+impl Output {
+	pub fn new<P>(root: P) -> Self
+	where
+		P: AsRef<Path>,
+	{
+		Output {
+			root: root.as_ref().to_owned(),
+			dry_run: false,
+		}
+	}
+
+	pub fn dry_run() -> Self {
+		Output {
+			root: PathBuf::new(),
+			dry_run: true,
+		}
+	}
+
+	pub fn write<P, C>(&self, path: P, contents: C) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+		C: AsRef<[u8]>,
+	{
+		if self.dry_run {
+			return Ok(());
+		}
+
+		let output_path = self.root.join(path);
+		fs::create_dir_all(output_path.parent().unwrap())?;
+		fs::write(output_path, contents)
+	}
+
+	pub fn remove<P>(&self, path: P) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+	{
+		if self.dry_run {
+			return Ok(());
+		}
+
+		match fs::remove_file(self.root.join(path)) {
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+			result => result,
+		}
+	}
+
+	pub fn copy<P, Q>(&self, from: P, to: Q) -> io::Result<()>
+	where
+		P: AsRef<Path>,
+		Q: AsRef<Path>,
+	{
+		if self.dry_run {
+			return Ok(());
+		}
+
+		let output_path = self.root.join(to);
+		fs::create_dir_all(output_path.parent().unwrap())?;
+		fs::copy(from.as_ref().canonicalize()?, output_path)?;
+		Ok(())
+	}
+}
+
+/// Builds the redirect for one of the `aliases` of the page at `path`, as long as it
+/// doesn't collide with an existing page or redirect
+fn alias_redirect(
+	alias: &str,
+	path: &Path,
+	pages: &HashSet<PathBuf>,
+	redirects: &[RedirectPage],
+) -> Result<RedirectPage, BuildError> {
+	if !alias.starts_with('/') {
+		return Err(
+			BuildError::new(format!("alias `{}` is not an absolute path", alias))
+				.with_hint("aliases should start with a `/`, like `/posts/gleam-traits.html`"),
+		);
+	}
+
+	let mut from = PathBuf::from(alias);
+	// Aliases for a directory redirect from its index
+	if alias.ends_with('/') {
+		from.push("index.html");
+	}
+
+	if pages.contains(from.strip_prefix("/").unwrap()) {
+		return Err(BuildError::new(format!(
+			"alias `{}` would overwrite an existing page",
+			alias
+		)));
+	}
+	if let Some(existing) = redirects.iter().find(|redirect| redirect.from == from) {
+		return Err(BuildError::new(format!(
+			"alias `{}` already redirects to `{}`",
+			alias, existing.to
+		)));
+	}
+
+	Ok(RedirectPage {
+		from,
+		to: format!("/{}", path.display()),
+	})
+}
+
+/// All of the content that makes up the site, loaded and ready to be rendered.
+#[derive(Clone, Debug)]
+pub struct Site {
+	pub publish: bool,
+	/// When publishing, posts dated after this day are held back until then
+	pub as_of: NaiveDate,
+	pub posts: Vec<BlogPost>,
+	/// Posts that would be published, if not for their date
+	pub scheduled: Vec<BlogPost>,
+	pub talks: Vec<Talk>,
+	/// The redirects from the site configuration, and from the `aliases` of every page
+	pub redirects: Vec<RedirectPage>,
+}
+
+impl Site {
+	/// Loads every post and talk. Pages that fail to load are left out, and the reasons
+	/// why are added to `diagnostics`, so that we can report all of them at once.
+	pub fn load(options: &Options, diagnostics: &mut Diagnostics) -> Self {
+		let as_of = options.as_of.unwrap_or_else(|| Local::now().date_naive());
+		Site::load_content(options.publish, as_of, diagnostics)
+	}
+
+	fn load_content(publish: bool, as_of: NaiveDate, diagnostics: &mut Diagnostics) -> Self {
+		let mut site = Site {
+			publish,
+			as_of,
+			posts: Vec::new(),
+			scheduled: Vec::new(),
+			talks: Vec::new(),
+			redirects: Vec::new(),
+		};
+
+		// Collect posts into a `PageCollection`, holding back any that aren't due yet
+		let posts = pages_from_directory("content/posts/")
+			.filter_map(|post| site.prepare_post(post, diagnostics))
+			.collect();
+		site.schedule(posts);
+
+		// Collect talks into a `PageCollection`
+		site.talks = pages_from_directory("content/talks/")
+			.filter_map(|talk| site.prepare_talk(talk, diagnostics))
+			.collect();
+
+		site.check_order(diagnostics);
+		site.collect_redirects(diagnostics);
+		site
+	}
+
+	/// Turns the `aliases` of every page into redirects, alongside the ones from the
+	/// site configuration. Aliases that would overwrite a real page, or that are already
+	/// taken by another redirect, are reported and left out.
+	fn collect_redirects(&mut self, diagnostics: &mut Diagnostics) {
+		let pages = self.output_paths();
+		let aliases = self
+			.posts
+			.iter()
+			.map(|post| (&post.path, &post.metadata.aliases))
+			.chain(
+				self
+					.talks
+					.iter()
+					.map(|talk| (&talk.path, &talk.metadata.aliases)),
+			);
+
+		let mut redirects = Vec::new();
+		for redirect in REDIRECTS.iter() {
+			match pages.contains(redirect.from.strip_prefix("/").unwrap_or(&redirect.from)) {
+				true => diagnostics.error(
+					BuildError::new(format!(
+						"redirect from `{}` would overwrite an existing page",
+						redirect.from.display()
+					))
+					.with_hint("remove it from the site configuration, or point the page somewhere else"),
+				),
+				false => redirects.push(redirect.clone()),
+			}
+		}
+		for (path, aliases) in aliases {
+			let source = Path::new("content/").join(path).with_extension("md");
+			for alias in aliases {
+				match alias_redirect(alias, path, &pages, &redirects) {
+					Ok(redirect) => redirects.push(redirect),
+					Err(error) => diagnostics.error(error.in_file(&source)),
+				}
+			}
+		}
+
+		redirects.sort_by(|a, b| a.from.cmp(&b.from));
+		self.redirects = redirects;
+	}
+
+	/// Every file that gets written to the output directory, other than redirects,
+	/// which shouldn't ever overwrite any of them
+	fn output_paths(&self) -> HashSet<PathBuf> {
+		let mut paths = [
+			"index.html",
+			"feed.xml",
+			"feed.json",
+			"rss.xml",
+			"sitemap.xml",
+			"robots.txt",
+			"search.html",
+			"search.json",
+			"talks/index.html",
+			"talks.xml",
+			"talks.ics",
+			"tags/index.html",
+			"resources/highlight.css",
+		]
+		.into_iter()
+		.map(PathBuf::from)
+		.collect::<HashSet<_>>();
+
+		for post in self.posts.iter() {
+			paths.insert(post.path.clone());
+			if has_preview(post) {
+				paths.insert(preview_path(&post.path));
+			}
+		}
+		for talk in self.talks.iter() {
+			paths.extend([talk.path.clone(), talk.notes_path(), talk.handout_path()]);
+		}
+		for tag in tags(&self.index_entries()) {
+			paths.extend([tag.path(), tag.feed_path()]);
+		}
+		let resources = fs::read_dir("content/resources/")
+			.into_iter()
+			.flatten()
+			.flatten()
+			.filter(|entry| entry.path().is_file());
+		for resource in resources {
+			paths.insert(Path::new("resources/").join(resource.file_name()));
+		}
+
+		paths
+	}
+
+	/// Warns about entries that share a date, time, and weight. They still end up in a
+	/// consistent order, but only because of their titles, which is rarely what anyone
+	/// actually wanted.
+	fn check_order(&self, diagnostics: &mut Diagnostics) {
+		let content = Path::new("content/");
+		let mut entries = self
+			.posts
+			.iter()
+			.map(|post| (post.sort_key(), Some(content.join(&post.path))))
+			.chain(
+				self
+					.talks
+					.iter()
+					.map(|talk| (talk.sort_key(), Some(content.join(&talk.path)))),
+			)
+			.chain(
+				EXTERNAL_LINKS
+					.iter()
+					.map(|external| (external.sort_key(), None)),
+			)
+			.collect::<Vec<_>>();
+		entries.sort_by_key(|(key, _)| *key);
+
+		for pair in entries.windows(2) {
+			let [(first, _), (second, source)] = pair else {
+				continue;
+			};
+			if !first.collides_with(second) {
+				continue;
+			}
+
+			let warning = BuildError::new(format!(
+				"`{}` and `{}` have the same date, so they are ordered by title",
+				first.title(),
+				second.title()
+			))
+			.with_hint("set a `time` or a `weight` on one of them to choose which comes first");
+			// Only a matter of taste, so it shouldn't stop the site from being published
+			diagnostics.note(match source {
+				Some(source) => warning.in_file(source.with_extension("md")),
+				None => warning,
+			});
+		}
+	}
+
+	fn prepare_post(
+		&self,
+		post: Result<BlogPost, BuildError>,
+		diagnostics: &mut Diagnostics,
+	) -> Option<BlogPost> {
+		let mut post = post.map_err(|error| diagnostics.error(error)).ok()?;
+		let source = post.path.with_extension("md");
+		post.path = post.path.strip_prefix("content/").unwrap().to_owned();
+		post.canonicalize();
+
+		if self.publish {
+			// Skip unpublished posts if we're building a version for publishing
+			if post.metadata.status != Published && post.metadata.status != Unlisted {
+				return None;
+			}
+
+			// Ensure that all of the posts have a date
+			if post.metadata.date.is_none() {
+				diagnostics.error(
+					BuildError::new("published posts must have a date")
+						.in_file(source)
+						.with_hint("add a `date` to the frontmatter, like `date: 2023.7.15`"),
+				);
+				return None;
+			}
+		}
+
+		post
+			.warnings
+			.drain(..)
+			.for_each(|warning| diagnostics.warn(warning));
+		Some(post)
+	}
+
+	/// Splits the posts that are due from the ones that are held back until later
+	fn schedule(&mut self, posts: Vec<BlogPost>) {
+		(self.scheduled, self.posts) = posts.into_iter().partition(|post| self.is_scheduled(post));
+		self.scheduled.sort_by_key(|post| post.metadata.date);
+	}
+
+	/// Whether a post is dated after the day that we're publishing as of
+	fn is_scheduled(&self, post: &BlogPost) -> bool {
+		self.publish && post.metadata.date.is_some_and(|date| date > self.as_of)
+	}
+
+	fn prepare_talk(
+		&self,
+		talk: Result<Talk, BuildError>,
+		diagnostics: &mut Diagnostics,
+	) -> Option<Talk> {
+		let mut talk = talk.map_err(|error| diagnostics.error(error)).ok()?;
+		talk.path = talk.path.strip_prefix("content/").unwrap().to_owned();
+		talk.canonicalize();
+		talk.upcoming = talk.metadata.date > self.as_of;
+
+		talk
+			.warnings
+			.drain(..)
+			.for_each(|warning| diagnostics.warn(warning));
+		Some(talk)
+	}
+
+	/// Renders every page of the site
+	pub fn render(&self, output: &Output) -> io::Result<()> {
+		self.render_redirects(output)?;
+		for post in self.posts.iter() {
+			self.render_post(post, output)?;
+		}
+		for talk in self.talks.iter() {
+			self.render_talk(talk, output)?;
+		}
+		self.render_index(output)?;
+		self.copy_resources(output)?;
+
+		Ok(())
+	}
+
+	/// Re-renders only the parts of the site affected by the given changed source files.
+	/// Anything that we can't attribute to a single page causes a full rebuild.
+	pub fn update(
+		&mut self,
+		changes: &[PathBuf],
+		output: &Output,
+		diagnostics: &mut Diagnostics,
+	) -> io::Result<()> {
+		let mut index_changed = false;
+		let redirects = self.redirects.clone();
+		let pages = self.output_paths();
+
+		for source in changes {
+			if source.starts_with("content/resources/") {
+				match source.is_file() {
+					true => self.copy_resource(source, output)?,
+					false => output.remove(Path::new("resources/").join(source.file_name().unwrap()))?,
+				}
+			} else if source.starts_with("content/posts/") {
+				let path = Self::output_path(source);
+				self.posts.retain(|post| post.path != path);
+				self.scheduled.retain(|post| post.path != path);
+				output.remove(&path)?;
+				output.remove(preview_path(&path))?;
+
+				if source.is_file() {
+					match self.prepare_post(BlogPost::from_file(source), diagnostics) {
+						Some(post) if self.is_scheduled(&post) => {
+							self.scheduled.push(post);
+							self.scheduled.sort_by_key(|post| post.metadata.date);
+						}
+						Some(post) => {
+							self.render_post(&post, output)?;
+							self.posts.push(post);
+						}
+						None => {}
+					}
+				}
+				index_changed = true;
+			} else if source.starts_with("content/talks/") {
+				let path = Self::output_path(source);
+				self.talks.retain(|talk| talk.path != path);
+				output.remove(&path)?;
+				output.remove(path.with_extension("notes.html"))?;
+				output.remove(path.with_extension("handout.html"))?;
+
+				if source.is_file() {
+					if let Some(talk) = self.prepare_talk(Talk::from_file(source), diagnostics) {
+						self.render_talk(&talk, output)?;
+						self.talks.push(talk);
+					}
+				}
+				index_changed = true;
+			} else {
+				*self = Site::load_content(self.publish, self.as_of, diagnostics);
+				return self.render(output);
+			}
+		}
+
+		if index_changed {
+			self.check_order(diagnostics);
+
+			// Tags that aren't used by anything any more leave their pages behind, so
+			// remove everything that a full build wouldn't generate
+			let current_pages = self.output_paths();
+			for page in pages.difference(&current_pages) {
+				output.remove(page)?;
+			}
+
+			// Aliases might have changed along with the pages, so clean up any redirects
+			// that are gone, unless a page has taken their place, and write out all of the
+			// current ones
+			self.collect_redirects(diagnostics);
+			for redirect in redirects.iter() {
+				let path = redirect.from.strip_prefix("/").unwrap();
+				if !self.redirects.contains(redirect) && !current_pages.contains(path) {
+					output.remove(path)?;
+				}
+			}
+			self.render_redirects(output)?;
+			self.render_index(output)?;
+		}
+
+		Ok(())
+	}
+
+	/// Where a page from the given source file ends up in the output directory
+	fn output_path(source: &Path) -> PathBuf {
+		let mut path = source.strip_prefix("content/").unwrap().to_owned();
+		path.set_extension("html");
+		path
+	}
+
+	pub fn render_redirects(&self, output: &Output) -> io::Result<()> {
+		for redirect in self.redirects.iter() {
+			let output_path = redirect
+				.from
+				.strip_prefix("/")
+				.expect("`from` for redirect should be an absolute url");
+			output.write(output_path, redirect.as_html())?;
+		}
+
+		Ok(())
+	}
+
+	pub fn render_post(&self, post: &BlogPost, output: &Output) -> io::Result<()> {
+		output.write(&post.path, post.as_html())?;
+
+		// Rasterizing the preview image is by far the slowest part of rendering a post,
+		// so don't bother if it won't be written anyway
+		if !has_preview(post) {
+			output.remove(preview_path(&post.path))?;
+		} else if !output.dry_run {
+			output.write(preview_path(&post.path), render_preview(post))?;
+		}
+
+		Ok(())
+	}
+
+	pub fn render_talk(&self, talk: &Talk, output: &Output) -> io::Result<()> {
+		output.write(&talk.path, talk.as_html())?;
+		output.write(talk.notes_path(), talk.notes_as_html())?;
+		output.write(talk.handout_path(), talk.handout_as_html())
+	}
+
+	/// Everything that shows up on the index, in order
+	fn index_entries(&self) -> BTreeSet<IndexEntry> {
+		// Hide unlisted posts from the index and RSS feeds
+		let post_entries = self
+			.posts
+			.iter()
+			.filter(|post| post.metadata.status != Unlisted)
+			.cloned()
+			.map(Into::into);
+		let talk_entries = self.talks.iter().cloned().map(Into::into);
+		let external_link_entries = EXTERNAL_LINKS.iter().cloned().map(Into::into);
+		post_entries
+			.chain(talk_entries)
+			.chain(external_link_entries)
+			.collect()
+	}
+
+	/// Renders the pages that are built from every post, like the index and the feed
+	pub fn render_index(&self, output: &Output) -> io::Result<()> {
+		let posts = self
+			.posts
+			.iter()
+			.filter(|post| post.metadata.status != Unlisted)
+			.collect::<Vec<_>>();
+		let index_entries = self.index_entries();
+
+		let renderer = Handlebars::new();
+		// Render index
+		let index_page = renderer
+			.render_template(
+				include_str!("./templates/index.html"),
+				&json!({
+					"blog": &*BLOG,
+					"posts": &index_entries,
+					"meta": Meta::site(&BLOG.title, BLOG.canonical_origin.as_str()).to_html(),
+				}),
+			)
+			.expect("failed to render handlebars");
+		output.write("index.html", index_page)?;
+		// Render the feeds
+		let post_entries = posts
+			.iter()
+			.cloned()
+			.cloned()
+			.map(IndexEntry::from)
+			.collect::<Vec<_>>();
+		let feed = Feed {
+			title: BLOG.title.clone(),
+			description: &BLOG.subtitle,
+			url: BLOG
+				.canonical_origin
+				.as_str()
+				.trim_end_matches('/')
+				.to_string(),
+			entries: post_entries.iter().map(FeedEntry::from).collect(),
+		};
+		output.write(
+			"feed.xml",
+			feed.atom(&config::canonicalize(Path::new("feed.xml"))),
+		)?;
+		output.write(
+			"feed.json",
+			feed.json(&config::canonicalize(Path::new("feed.json"))),
+		)?;
+		output.write(
+			"rss.xml",
+			feed.rss(&config::canonicalize(Path::new("rss.xml"))),
+		)?;
+
+		self.render_sitemap(output)?;
+		self.render_search(output)?;
+		self.render_talks(output)?;
+		self.render_tags(&index_entries, output)
+	}
+
+	/// Renders the list of talks, along with a feed of the ones that have been given,
+	/// and a calendar of all of them
+	pub fn render_talks(&self, output: &Output) -> io::Result<()> {
+		let mut talks = self.talks.iter().collect::<Vec<_>>();
+		talks.sort_by_key(|talk| talk.sort_key());
+
+		let talks_page = Handlebars::new()
+			.render_template(
+				include_str!("./templates/talks.html"),
+				&json!({
+					"blog": &*BLOG,
+					"talks": &talks,
+					"meta": Meta::site(
+						&format!("Talks \u{2014} {}", BLOG.title),
+						config::canonicalize(Path::new("talks/")).as_str(),
+					)
+					.to_html(),
+				}),
+			)
+			.expect("failed to render handlebars");
+		output.write("talks/index.html", talks_page)?;
+
+		let calendar = Calendar {
+			name: format!("{} \u{2014} Talks", BLOG.title),
+			events: talks.iter().copied().map(CalendarEvent::from).collect(),
+		};
+		output.write("talks.ics", calendar.to_ics(Utc::now()))?;
+
+		let talk_entries = talks
+			.into_iter()
+			.filter(|talk| !talk.upcoming)
+			.cloned()
+			.map(IndexEntry::from)
+			.collect::<Vec<_>>();
+		let feed = Feed {
+			title: format!("{} \u{2014} Talks", BLOG.title),
+			description: &BLOG.subtitle,
+			url: config::canonicalize(Path::new("talks/")).to_string(),
+			entries: talk_entries.iter().map(FeedEntry::from).collect(),
+		};
+		output.write(
+			"talks.xml",
+			feed.atom(&config::canonicalize(Path::new("talks.xml"))),
+		)
+	}
+
+	/// Renders the search page, along with the index that it queries
+	pub fn render_search(&self, output: &Output) -> io::Result<()> {
+		let index = SearchIndex::new(&self.posts, &self.talks);
+		output.write("search.json", index.to_json())?;
+
+		let search_page = Handlebars::new()
+			.render_template(
+				include_str!("./templates/search.html"),
+				&json!({
+					"blog": &*BLOG,
+					"stop_words": serde_json::to_string(STOP_WORDS).unwrap(),
+					"meta": Meta::site(
+						&format!("Search \u{2014} {}", BLOG.title),
+						config::canonicalize(Path::new("search.html")).as_str(),
+					)
+					.to_html(),
+				}),
+			)
+			.expect("failed to render handlebars");
+		output.write("search.html", search_page)
+	}
+
+	/// Renders the sitemap, and a robots.txt that points crawlers to it. Unlisted posts
+	/// are left out, since they aren't meant to be found without a link.
+	pub fn render_sitemap(&self, output: &Output) -> io::Result<()> {
+		let posts = self
+			.posts
+			.iter()
+			.filter(|post| post.metadata.status != Unlisted)
+			.map(|post| (post.path.clone(), post.metadata.date));
+		// The handout of a talk is much easier to index than the slides, and neither
+		// is worth indexing until the talk has been given
+		let talks = self
+			.talks
+			.iter()
+			.filter(|talk| !talk.upcoming)
+			.flat_map(|talk| {
+				[talk.path.clone(), talk.handout_path()].map(|path| (path, Some(talk.metadata.date)))
+			});
+		let pages = posts
+			.chain(talks)
+			// Redirects only point somewhere else, so they shouldn't be indexed themselves
+			.filter(|(path, _)| {
+				!self
+					.redirects
+					.iter()
+					.any(|redirect| redirect.from.strip_prefix("/").ok() == Some(path.as_path()))
+			})
+			.collect::<Vec<_>>();
+
+		let mut sitemap = Sitemap::default();
+		sitemap.add(
+			&BLOG.canonical_origin,
+			pages.iter().filter_map(|(_, date)| *date).max(),
+		);
+		for (path, date) in pages {
+			sitemap.add(config::canonicalize(&path), date);
+		}
+
+		let sitemap_url = config::canonicalize(Path::new("sitemap.xml"));
+		output.write("sitemap.xml", sitemap.to_xml())?;
+		output.write("robots.txt", robots_txt(&sitemap_url))
+	}
+
+	/// Renders the tag overview, along with a page and a feed for each tag
+	pub fn render_tags(
+		&self,
+		index_entries: &BTreeSet<IndexEntry>,
+		output: &Output,
+	) -> io::Result<()> {
+		let tags = tags(index_entries);
+
+		let renderer = Handlebars::new();
+		let tags_page = renderer
+			.render_template(
+				include_str!("./templates/tags.html"),
+				&json!({
+					"blog": &*BLOG,
+					"tags": &tags,
+					"meta": Meta::site(
+						&format!("Tags \u{2014} {}", BLOG.title),
+						config::canonicalize(Path::new("tags/")).as_str(),
+					)
+					.to_html(),
+				}),
+			)
+			.expect("failed to render handlebars");
+		output.write("tags/index.html", tags_page)?;
+
+		for tag in tags.iter() {
+			let tag_page = renderer
+				.render_template(
+					include_str!("./templates/tag.html"),
+					&json!({
+						"blog": &*BLOG,
+						"tag": tag,
+						"meta": Meta::site(
+							&format!("{} \u{2014} {}", tag.name, BLOG.title),
+							config::canonicalize(&tag.path()).as_str(),
+						)
+						.to_html(),
+					}),
+				)
+				.expect("failed to render handlebars");
+			output.write(tag.path(), tag_page)?;
+
+			let feed = Feed {
+				title: format!("{} \u{2014} {}", BLOG.title, tag.name),
+				description: &BLOG.subtitle,
+				url: config::canonicalize(&tag.path()).to_string(),
+				entries: tag
+					.entries
+					.iter()
+					.copied()
+					.filter(|entry| !entry.is_upcoming())
+					.map(FeedEntry::from)
+					.collect(),
+			};
+			let tag_feed = feed.atom(&config::canonicalize(&tag.feed_path()));
+			output.write(tag.feed_path(), tag_feed)?;
+		}
+
+		Ok(())
+	}
+
+	/// Copy assets from content/resources/ to the output/resources/ directory, along
+	/// with the stylesheet for highlighted code
+	pub fn copy_resources(&self, output: &Output) -> io::Result<()> {
+		output.write("resources/highlight.css", md::highlight_css())?;
+
+		for file in fs::read_dir("content/resources/")?
+			.flatten()
+			.map(|entry| entry.path())
+			.filter(|path| path.is_file())
+		{
+			self.copy_resource(&file, output)?;
+		}
+
+		Ok(())
+	}
+
+	pub fn copy_resource(&self, file: &Path, output: &Output) -> io::Result<()> {
+		output.copy(
+			file,
+			Path::new("resources/").join(file.file_name().expect("failed to get file name")),
+		)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn alias_conflicts() {
+		let path = Path::new("posts/all-you-need-is-data-and-functions.html");
+		let pages = HashSet::from([
+			path.to_owned(),
+			PathBuf::from("posts/cool-video-games.html"),
+		]);
+		let redirects = vec![RedirectPage {
+			from: PathBuf::from("/posts/old.html"),
+			to: "/posts/cool-video-games.html".to_string(),
+		}];
+
+		let redirect = alias_redirect("/posts/gleam-traits.html", path, &pages, &redirects).unwrap();
+		assert_eq!(redirect.from, PathBuf::from("/posts/gleam-traits.html"));
+		assert_eq!(
+			redirect.to,
+			"/posts/all-you-need-is-data-and-functions.html"
+		);
+
+		let redirect = alias_redirect("/gleam/", path, &pages, &redirects).unwrap();
+		assert_eq!(redirect.from, PathBuf::from("/gleam/index.html"));
+
+		assert!(alias_redirect("posts/gleam-traits.html", path, &pages, &redirects).is_err());
+		assert!(alias_redirect("/posts/cool-video-games.html", path, &pages, &redirects).is_err());
+		assert!(alias_redirect("/posts/old.html", path, &pages, &redirects).is_err());
+	}
+
+	fn post(title: &str, path: &str, aliases: &str) -> BlogPost {
+		post_with(
+			path,
+			&format!("title: {}\ndate: 2023.7.22\naliases: [{}]", title, aliases),
+		)
+	}
+
+	fn post_with(path: &str, frontmatter: &str) -> BlogPost {
+		let metadata =
+			serde_yaml::from_str(&format!("author: Kayla Washburn\n{}", frontmatter)).unwrap();
+		BlogPost {
+			canonical_url: config::canonicalize(Path::new(path)),
+			path: PathBuf::from(path),
+			metadata,
+			content: String::new(),
+			source: String::new(),
+			toc: Vec::new(),
+			excerpt: None,
+			warnings: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn strict_build_allows_ties() {
+		let site = Site {
+			publish: true,
+			as_of: NaiveDate::from_ymd_opt(2023, 7, 22).unwrap(),
+			posts: vec![post("A", "posts/a.html", ""), post("B", "posts/b.html", "")],
+			scheduled: Vec::new(),
+			talks: Vec::new(),
+			redirects: Vec::new(),
+		};
+		let mut diagnostics = Diagnostics::new(true);
+		site.check_order(&mut diagnostics);
+
+		assert!(diagnostics.errors.is_empty());
+		assert_eq!(diagnostics.warnings.len(), 1);
+	}
+
+	#[test]
+	fn aliases_collide_with_generated_pages() {
+		let mut site = Site {
+			publish: false,
+			as_of: NaiveDate::from_ymd_opt(2023, 7, 22).unwrap(),
+			posts: vec![post(
+				"A",
+				"posts/a.html",
+				"/search.html, /talks/, /og/a.png, /posts/old.html",
+			)],
+			scheduled: Vec::new(),
+			talks: Vec::new(),
+			redirects: Vec::new(),
+		};
+		let mut diagnostics = Diagnostics::new(false);
+		site.collect_redirects(&mut diagnostics);
+
+		assert_eq!(diagnostics.errors.len(), 3);
+		assert_eq!(site.redirects.len(), 1);
+		assert_eq!(site.redirects[0].from, PathBuf::from("/posts/old.html"));
+	}
+
+	#[test]
+	fn holds_back_scheduled_posts() {
+		let posts = || {
+			["2023.7.21", "2023.7.22", "2023.7.23"]
+				.into_iter()
+				.map(|date| {
+					post_with(
+						&format!("posts/{}.html", date),
+						&format!(
+							"title: Post from {}\ndate: {}\nstatus: published",
+							date, date
+						),
+					)
+				})
+				.collect::<Vec<_>>()
+		};
+		let root = std::env::temp_dir().join(format!("scheduled-posts-{}", std::process::id()));
+		let output = Output::new(&root);
+		let published = |path: &str| {
+			let listings = [
+				"index.html",
+				"feed.xml",
+				"feed.json",
+				"rss.xml",
+				"sitemap.xml",
+			]
+			.map(|listing| fs::read_to_string(root.join(listing)).unwrap());
+			let rendered = root.join(path).is_file();
+			let listed = listings
+				.iter()
+				.filter(|listing| listing.contains(path))
+				.count();
+			assert!(
+				listed == 0 || listed == listings.len(),
+				"{} is only in some listings",
+				path
+			);
+			rendered && listed > 0
+		};
+
+		let mut site = Site {
+			publish: true,
+			as_of: NaiveDate::from_ymd_opt(2023, 7, 22).unwrap(),
+			posts: Vec::new(),
+			scheduled: Vec::new(),
+			talks: Vec::new(),
+			redirects: Vec::new(),
+		};
+		site.schedule(posts());
+		site.render(&output).unwrap();
+		assert!(published("posts/2023.7.21.html"));
+		assert!(published("posts/2023.7.22.html"));
+		assert!(!published("posts/2023.7.23.html"));
+		assert!(!root.join("posts/2023.7.23.html").exists());
+		assert_eq!(site.scheduled.len(), 1);
+
+		// Publishing as of a later day lets the post through
+		fs::remove_dir_all(&root).unwrap();
+		site.as_of = NaiveDate::from_ymd_opt(2023, 7, 23).unwrap();
+		site.schedule(posts());
+		site.render(&output).unwrap();
+		assert!(published("posts/2023.7.23.html"));
+		assert!(site.scheduled.is_empty());
+
+		// Nothing gets held back outside of publishing
+		fs::remove_dir_all(&root).unwrap();
+		site.publish = false;
+		site.as_of = NaiveDate::from_ymd_opt(2023, 7, 1).unwrap();
+		site.schedule(posts());
+		site.render(&output).unwrap();
+		assert!(published("posts/2023.7.23.html"));
+		assert!(site.scheduled.is_empty());
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+
+	fn talk(path: &str, frontmatter: &str) -> Talk {
+		let metadata =
+			serde_yaml::from_str(&format!("author: Kayla Washburn\n{}", frontmatter)).unwrap();
+		Talk {
+			canonical_url: config::canonicalize(Path::new(path)),
+			path: PathBuf::from(path),
+			metadata,
+			slides: Vec::new(),
+			toc: Vec::new(),
+			slide_count: 1,
+			upcoming: false,
+			warnings: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn renders_talks() {
+		let site = Site {
+			publish: false,
+			as_of: NaiveDate::from_ymd_opt(2023, 7, 22).unwrap(),
+			posts: Vec::new(),
+			scheduled: Vec::new(),
+			talks: vec![
+				talk(
+					"talks/recorded.html",
+					"title: Recorded talk\ndate: 2023.7.15\nyoutube: https://youtu.be/UQ-7k8uAc3c",
+				),
+				talk(
+					"talks/unrecorded.html",
+					"title: Unrecorded talk\ndate: 2023.6.1",
+				),
+			],
+			redirects: Vec::new(),
+		};
+		let root = std::env::temp_dir().join(format!("talks-{}", std::process::id()));
+		site.render_talks(&Output::new(&root)).unwrap();
+
+		let talks_page = fs::read_to_string(root.join("talks/index.html")).unwrap();
+		assert!(talks_page.contains(r#"<a href="talks/recorded.html"><h1>Recorded talk</h1></a>"#));
+		assert!(talks_page.contains(r#"<a href="talks/unrecorded.html"><h1>Unrecorded talk</h1></a>"#));
+		assert_eq!(talks_page.matches("Watch the recording").count(), 1);
+		assert!(
+			talks_page.contains(r#"<a href="https://youtu.be/UQ-7k8uAc3c">Watch the recording</a>"#)
+		);
+
+		let feed = fs::read_to_string(root.join("talks.xml")).unwrap();
+		assert_eq!(feed.matches("<entry>").count(), 2);
+		assert_eq!(feed.matches(r#"rel="related""#).count(), 1);
+		assert!(feed
+			.contains(r#"<link rel="related" title="Recording" href="https://youtu.be/UQ-7k8uAc3c"/>"#));
+		assert!(feed.contains(r#"<link href="https://mckayla.blog/talks/unrecorded.html"/>"#));
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+
+	#[test]
+	fn update_removes_orphaned_tags() {
+		let mut site = Site {
+			publish: false,
+			as_of: NaiveDate::from_ymd_opt(2023, 7, 22).unwrap(),
+			posts: vec![
+				post_with(
+					"posts/update-test-lonely.html",
+					"title: Lonely\ndate: 2023.7.1\ntags: [lonely, shared]",
+				),
+				post_with("posts/a.html", "title: A\ndate: 2023.7.22\ntags: [shared]"),
+				post_with("posts/b.html", "title: B\ndate: 2023.7.22\ntags: [shared]"),
+			],
+			scheduled: Vec::new(),
+			talks: Vec::new(),
+			redirects: Vec::new(),
+		};
+		let root = std::env::temp_dir().join(format!("update-tags-{}", std::process::id()));
+		let output = Output::new(&root);
+		site.render(&output).unwrap();
+		assert!(root.join("tags/lonely.html").is_file());
+
+		// The source file doesn't exist, so the post counts as deleted
+		let mut diagnostics = Diagnostics::new(false);
+		site
+			.update(
+				&[PathBuf::from("content/posts/update-test-lonely.md")],
+				&output,
+				&mut diagnostics,
+			)
+			.unwrap();
+
+		assert!(!root.join("posts/update-test-lonely.html").exists());
+		assert!(!root.join("tags/lonely.html").exists());
+		assert!(!root.join("tags/lonely.xml").exists());
+		assert!(root.join("tags/shared.html").is_file());
+		assert!(root.join("tags/shared.xml").is_file());
+		// The tie between A and B gets reported again
+		assert_eq!(diagnostics.warnings.len(), 1);
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+}
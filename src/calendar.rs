@@ -0,0 +1,195 @@
+use chrono::DateTime;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::NaiveDateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+
+use crate::pocky::md;
+use crate::talk::Talk;
+
+/// A calendar, as described at https://www.rfc-editor.org/rfc/rfc5545, which calendar
+/// apps can subscribe to
+#[derive(Clone, Debug)]
+pub struct Calendar {
+	pub name: String,
+	pub events: Vec<CalendarEvent>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CalendarEvent {
+	/// Stays the same across builds, so that calendars can update the event
+	pub uid: String,
+	pub summary: String,
+	pub start: EventStart,
+	pub location: Option<String>,
+	pub description: Option<String>,
+	pub url: String,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EventStart {
+	/// Lasts the whole day
+	Date(NaiveDate),
+	/// Happens at the same time of day, wherever the reader is
+	Floating(NaiveDateTime),
+	Utc(DateTime<Utc>),
+}
+
+/// Talks don't say how long they are, but most of them fit in an hour
+const TALK_LENGTH: &str = "PT1H";
+
+/// Escapes text so that it can be used as the value of a property
+fn escape(text: &str) -> String {
+	text
+		.replace('\\', "\\\\")
+		.replace(';', "\\;")
+		.replace(',', "\\,")
+		.replace('\n', "\\n")
+}
+
+/// Lines can be at most 75 bytes long, and continue on the next line after a space
+fn fold(line: &str) -> String {
+	let mut folded = String::new();
+	let mut length = 0;
+	for char in line.chars() {
+		if length + char.len_utf8() > 75 {
+			folded.push_str("\r\n ");
+			length = 1;
+		}
+		folded.push(char);
+		length += char.len_utf8();
+	}
+	folded.push_str("\r\n");
+	folded
+}
+
+impl From<&Talk> for CalendarEvent {
+	fn from(talk: &Talk) -> Self {
+		let metadata = &talk.metadata;
+		let start = match (metadata.time, metadata.timezone) {
+			(None, _) => EventStart::Date(metadata.date),
+			(Some(time), None) => EventStart::Floating(metadata.date.and_time(time)),
+			// Times that get skipped over by daylight saving time don't exist, so
+			// the best we can do is leave them as they were written
+			(Some(time), Some(timezone)) => timezone
+				.from_local_datetime(&metadata.date.and_time(time))
+				.earliest()
+				.map(|start| EventStart::Utc(start.with_timezone(&Utc)))
+				.unwrap_or(EventStart::Floating(metadata.date.and_time(time))),
+		};
+
+		let description = [
+			metadata.event.as_ref().map(|event| format!("At {}", event)),
+			metadata.summary.as_deref().map(md::html_to_text),
+			metadata
+				.youtube
+				.as_ref()
+				.map(|youtube| format!("Watch the recording: {}", youtube)),
+		]
+		.into_iter()
+		.flatten()
+		.collect::<Vec<_>>();
+
+		CalendarEvent {
+			uid: talk.canonical_url.to_string(),
+			summary: metadata.title.clone(),
+			start,
+			location: metadata.venue.clone(),
+			description: (!description.is_empty()).then(|| description.join("\n\n")),
+			// The slides of upcoming talks aren't up yet, so the event is more useful
+			url: match &metadata.event_url {
+				Some(event_url) if talk.upcoming => event_url.clone(),
+				_ => talk.canonical_url.to_string(),
+			},
+		}
+	}
+}
+
+impl Calendar {
+	/// Renders the calendar, marking every event as last changed at `now`
+	pub fn to_ics(&self, now: DateTime<Utc>) -> String {
+		let stamp = now.format("%Y%m%dT%H%M%SZ");
+		let mut lines = vec![
+			"BEGIN:VCALENDAR".to_string(),
+			"VERSION:2.0".to_string(),
+			"PRODID:-//mckayla.blog//talks//EN".to_string(),
+			format!("X-WR-CALNAME:{}", escape(&self.name)),
+		];
+		for event in self.events.iter() {
+			lines.push("BEGIN:VEVENT".to_string());
+			lines.push(format!("UID:{}", event.uid));
+			lines.push(format!("DTSTAMP:{}", stamp));
+			match event.start {
+				EventStart::Date(date) => {
+					lines.push(format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")));
+					lines.push(format!(
+						"DTEND;VALUE=DATE:{}",
+						(date + Duration::days(1)).format("%Y%m%d")
+					));
+				}
+				EventStart::Floating(start) => {
+					lines.push(format!("DTSTART:{}", start.format("%Y%m%dT%H%M%S")));
+					lines.push(format!("DURATION:{}", TALK_LENGTH));
+				}
+				EventStart::Utc(start) => {
+					lines.push(format!("DTSTART:{}", start.format("%Y%m%dT%H%M%SZ")));
+					lines.push(format!("DURATION:{}", TALK_LENGTH));
+				}
+			}
+			lines.push(format!("SUMMARY:{}", escape(&event.summary)));
+			if let Some(location) = &event.location {
+				lines.push(format!("LOCATION:{}", escape(location)));
+			}
+			if let Some(description) = &event.description {
+				lines.push(format!("DESCRIPTION:{}", escape(description)));
+			}
+			lines.push(format!("URL:{}", event.url));
+			lines.push("END:VEVENT".to_string());
+		}
+		lines.push("END:VCALENDAR".to_string());
+
+		lines.iter().map(|line| fold(line)).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn writes_events() {
+		let date = NaiveDate::from_ymd_opt(2023, 7, 15).unwrap();
+		let calendar = Calendar {
+			name: "Talks".to_string(),
+			events: vec![
+				CalendarEvent {
+					uid: "https://mckayla.blog/talks/cool-video-games.html".to_string(),
+					summary: "Cool video games; and more".to_string(),
+					start: EventStart::Date(date),
+					location: Some("Denver, CO".to_string()),
+					description: Some("a".repeat(100)),
+					url: "https://mckayla.blog/talks/cool-video-games.html".to_string(),
+				},
+				CalendarEvent {
+					uid: "https://mckayla.blog/talks/package-management.html".to_string(),
+					summary: "Package management".to_string(),
+					start: EventStart::Utc(Utc.with_ymd_and_hms(2023, 7, 15, 15, 30, 0).unwrap()),
+					location: None,
+					description: None,
+					url: "https://mckayla.blog/talks/package-management.html".to_string(),
+				},
+			],
+		};
+		let ics = calendar.to_ics(Utc.with_ymd_and_hms(2023, 7, 1, 0, 0, 0).unwrap());
+
+		assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+		assert!(ics.contains("DTSTART;VALUE=DATE:20230715\r\nDTEND;VALUE=DATE:20230716\r\n"));
+		assert!(ics.contains("SUMMARY:Cool video games\\; and more\r\n"));
+		assert!(ics.contains("LOCATION:Denver\\, CO\r\n"));
+		assert!(ics.contains("DTSTART:20230715T153000Z\r\nDURATION:PT1H\r\n"));
+		assert!(ics.contains("DTSTAMP:20230701T000000Z\r\n"));
+		assert!(ics.lines().all(|line| line.len() <= 75));
+		assert!(ics.ends_with("END:VCALENDAR\r\n"));
+	}
+}
@@ -1,42 +1,63 @@
 use chrono::Utc;
 use once_cell::sync::Lazy;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use serde::Deserializer;
 use serde::Serialize;
 use serde_json::json;
 use std::collections::BTreeSet;
 use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
 use url::Url;
 
 use crate::external::External;
+use crate::pocky::fields::unknown_fields;
+use crate::pocky::fields::UnknownField;
+use crate::pocky::frontmatter::Format;
+use crate::pocky::frontmatter::Frontmatter;
+use crate::pocky::BuildError;
+use crate::pocky::Diagnostics;
 use crate::redirect_config;
 use crate::redirect_page::RedirectPage;
 
-#[derive(Clone, Debug, Serialize)]
+/// Where we look for a site configuration file when one isn't given explicitly
+const CONFIG_FILES: &[&str] = &["site.toml", "site.yaml", "site.yml"];
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
 pub struct BlogMetadata {
 	pub canonical_origin: Url,
-	pub favicon: &'static str,
-	pub thumbnail: &'static str,
-	pub title: &'static str,
-	pub subtitle: &'static str,
-	pub og_title: &'static str,
-	pub og_image: &'static str,
+	pub favicon: String,
+	pub thumbnail: String,
+	pub title: String,
+	pub subtitle: String,
+	pub og_title: String,
+	pub og_image: String,
+	#[serde(skip_deserializing)]
 	pub updated: String,
 }
 
-pub static BLOG: Lazy<BlogMetadata> = Lazy::new(|| BlogMetadata {
-	canonical_origin: Url::parse("https://mckayla.blog").unwrap(),
-	favicon: "https://cdn.mckayla.cloud/-/764b1512ee1f490a951e9c00d9ded4b2/Doodle.avif",
-	thumbnail: "https://cdn.mckayla.cloud/-/764b1512ee1f490a951e9c00d9ded4b2/Doodle.avif",
-	title: "Kayla",
-	subtitle: "Aspiring wannabe, human shaped, slightly minty • she/her 🏳️‍⚧️",
-	og_title: "mckayla.blog",
-	og_image: "https://cdn.mckayla.cloud/-/97ef05b2b92b44c687dfcccfb32dff16/cute3.avif",
-	updated: Utc::now().format("%Y-%m-%dT%H:%M:00.000Z").to_string(),
-});
+impl Default for BlogMetadata {
+	fn default() -> Self {
+		BlogMetadata {
+			canonical_origin: Url::parse("https://mckayla.blog").unwrap(),
+			favicon: "https://cdn.mckayla.cloud/-/764b1512ee1f490a951e9c00d9ded4b2/Doodle.avif".into(),
+			thumbnail: "https://cdn.mckayla.cloud/-/764b1512ee1f490a951e9c00d9ded4b2/Doodle.png".into(),
+			title: "Kayla".into(),
+			subtitle: "Aspiring wannabe, human shaped, slightly minty • she/her 🏳️‍⚧️".into(),
+			og_title: "mckayla.blog".into(),
+			og_image: "https://cdn.mckayla.cloud/-/97ef05b2b92b44c687dfcccfb32dff16/cute3.avif".into(),
+			updated: Utc::now().format("%Y-%m-%dT%H:%M:00.000Z").to_string(),
+		}
+	}
+}
 
 // * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
-// * Configure external posts here!!                                                     *
+// * These are only the defaults! Configure the site in site.toml                       *
 // * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
-pub static EXTERNAL_LINKS: Lazy<BTreeSet<External>> = Lazy::new(|| {
+fn default_external_links() -> BTreeSet<External> {
 	serde_json::from_value(json!([
 		// {
 		// 	"canonical_url": "https://xaslilac.github.io/TeenageWeb/",
@@ -48,11 +69,365 @@ pub static EXTERNAL_LINKS: Lazy<BTreeSet<External>> = Lazy::new(|| {
 		// },
 	]))
 	.expect("invalid external link set")
-});
+}
 
-// * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
-// * Configure redirects here!!                                                          *
-// * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
-pub static REDIRECTS: Lazy<HashSet<RedirectPage>> = redirect_config!(
-	"/posts/gleam-traits.html" => "/posts/all-you-need-is-data-and-functions.html",
+// Renamed pages should usually set `aliases` in their frontmatter instead
+static DEFAULT_REDIRECTS: Lazy<HashSet<RedirectPage>> = redirect_config!(
+	// "/posts/gleam-traits.html" => "/posts/all-you-need-is-data-and-functions.html",
 );
+
+/// How much of each entry goes into the feeds
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedContent {
+	/// Only the summary, so readers have to visit the site for the rest
+	#[default]
+	Summary,
+	/// The whole rendered entry, along with the summary
+	Full,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FeedConfig {
+	pub content: FeedContent,
+}
+
+/// Everything about the site that can be changed without recompiling
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct SiteConfig {
+	pub blog: BlogMetadata,
+	pub external_links: BTreeSet<External>,
+	pub feed: FeedConfig,
+	#[serde(deserialize_with = "unique_redirects")]
+	pub redirects: HashSet<RedirectPage>,
+}
+
+impl Default for SiteConfig {
+	fn default() -> Self {
+		SiteConfig {
+			blog: BlogMetadata::default(),
+			external_links: default_external_links(),
+			feed: FeedConfig::default(),
+			redirects: DEFAULT_REDIRECTS.clone(),
+		}
+	}
+}
+
+/// Redirects are kept in a set, so we need to check for duplicates before they get
+/// quietly collapsed into one
+fn unique_redirects<'de, D>(de: D) -> Result<HashSet<RedirectPage>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	let mut redirects = HashSet::new();
+	for redirect in Vec::<RedirectPage>::deserialize(de)? {
+		let from = redirect.from.display().to_string();
+		if !redirects.insert(redirect) {
+			return Err(serde::de::Error::custom(format!(
+				"more than one redirect from `{}`",
+				from
+			)));
+		}
+	}
+
+	Ok(redirects)
+}
+
+impl SiteConfig {
+	/// Loads the configuration from `path`, or from one of `CONFIG_FILES` if no path
+	/// is given. If there is no configuration file at all, or if it's invalid, the
+	/// compiled-in defaults are used, and any problems are added to `diagnostics`.
+	pub fn load(path: Option<&Path>, diagnostics: &mut Diagnostics) -> Self {
+		let path = match path {
+			Some(path) => path.to_owned(),
+			None => match CONFIG_FILES
+				.iter()
+				.map(PathBuf::from)
+				.find(|path| path.is_file())
+			{
+				Some(path) => path,
+				None => return SiteConfig::default(),
+			},
+		};
+
+		match SiteConfig::from_file(&path) {
+			Ok((config, warnings)) => {
+				warnings
+					.into_iter()
+					.for_each(|warning| diagnostics.warn(warning));
+				let errors = config.validate();
+				if errors.is_empty() {
+					return config;
+				}
+				for error in errors {
+					diagnostics.error(error.in_file(&path));
+				}
+			}
+			Err(error) => diagnostics.error(error),
+		}
+
+		SiteConfig::default()
+	}
+
+	/// Parses the configuration, along with warnings about any keys that we don't know
+	/// about, which `diagnostics` only treats as fatal when publishing
+	fn from_file(path: &Path) -> Result<(Self, Vec<BuildError>), BuildError> {
+		let format = match path.extension().and_then(|ext| ext.to_str()) {
+			Some("toml") => Format::Toml,
+			Some("yaml" | "yml") => Format::Yaml,
+			Some("json") => Format::Json,
+			_ => {
+				return Err(
+					BuildError::new("unrecognized configuration format")
+						.in_file(path)
+						.with_hint("the site configuration can be written in TOML, YAML, or JSON"),
+				)
+			}
+		};
+
+		let source = fs::read_to_string(path)
+			.map_err(|err| BuildError::new(format!("unable to read file: {}", err)).in_file(path))?;
+		let config = Frontmatter {
+			name: "site configuration",
+			format,
+			source: &source,
+			line_offset: 0,
+		};
+
+		let (site_config, mut warnings) = config
+			.deserialize::<SiteConfig>()
+			.map_err(|err| err.in_file(path))?;
+
+		// Only the top level keys get checked while deserializing, so the tables and
+		// lists inside of it need to be checked separately
+		let value = config.value::<serde_json::Value>().unwrap_or_default();
+		let mut unknown = Vec::<(UnknownField, String)>::new();
+		for table in entries(&value, "blog") {
+			let fields = unknown_fields::<BlogMetadata, _>(table.keys().map(String::as_str));
+			unknown.extend(fields.into_iter().map(|field| (field, "`[blog]`".into())));
+		}
+		for table in entries(&value, "feed") {
+			let fields = unknown_fields::<FeedConfig, _>(table.keys().map(String::as_str));
+			unknown.extend(fields.into_iter().map(|field| (field, "`[feed]`".into())));
+		}
+		for entry in entries(&value, "redirects") {
+			let from = entry.get("from").and_then(|from| from.as_str());
+			let place = format!("redirect from `{}`", from.unwrap_or_default());
+			let fields = unknown_fields::<RedirectPage, _>(entry.keys().map(String::as_str));
+			unknown.extend(fields.into_iter().map(|field| (field, place.clone())));
+		}
+		for entry in entries(&value, "external_links") {
+			let title = entry.get("title").and_then(|title| title.as_str());
+			let place = format!("external link `{}`", title.unwrap_or("without a title"));
+			let fields = External::unknown_fields(entry.keys().map(String::as_str));
+			unknown.extend(fields.into_iter().map(|field| (field, place.clone())));
+		}
+		for (field, place) in unknown {
+			let warning = BuildError::new(format!("unknown field `{}` in {}", field.key, place));
+			warnings.push(match field.hint() {
+				Some(hint) => warning.with_hint(hint),
+				None => warning,
+			});
+		}
+
+		let warnings = warnings
+			.into_iter()
+			.map(|warning| warning.in_file(path))
+			.collect();
+		Ok((site_config, warnings))
+	}
+
+	/// Checks for things that deserialize fine, but would still produce a broken site
+	fn validate(&self) -> Vec<BuildError> {
+		let mut errors = Vec::new();
+
+		let origin = &self.blog.canonical_origin;
+		if !matches!(origin.scheme(), "http" | "https") || origin.path() != "/" {
+			errors.push(
+				BuildError::new(format!("invalid canonical_origin `{}`", origin))
+					.with_hint("the origin should look like `https://mckayla.blog`, without a path"),
+			);
+		}
+
+		for redirect in self.redirects.iter() {
+			if !redirect.from.starts_with("/") {
+				errors.push(
+					BuildError::new(format!(
+						"redirect from `{}` is not an absolute path",
+						redirect.from.display()
+					))
+					.with_hint("redirects should start with a `/`, like `/posts/gleam-traits.html`"),
+				);
+			}
+			if redirect.to.is_empty() {
+				errors.push(BuildError::new(format!(
+					"redirect from `{}` doesn't go anywhere",
+					redirect.from.display()
+				)));
+			}
+		}
+
+		errors
+	}
+}
+
+/// The objects under `key`, whether it's a single table or a list of them
+fn entries<'v>(
+	value: &'v serde_json::Value,
+	key: &str,
+) -> Vec<&'v serde_json::Map<String, serde_json::Value>> {
+	match value.get(key) {
+		Some(serde_json::Value::Object(table)) => vec![table],
+		Some(serde_json::Value::Array(entries)) => entries
+			.iter()
+			.filter_map(|entry| entry.as_object())
+			.collect(),
+		_ => Vec::new(),
+	}
+}
+
+static SITE: OnceCell<SiteConfig> = OnceCell::new();
+
+/// Sets the configuration for the rest of the build. Anything that reads the
+/// configuration before this is called will get the defaults instead.
+pub fn init(config: SiteConfig) {
+	if SITE.set(config).is_err() {
+		panic!("site configuration was already initialized");
+	}
+}
+
+pub fn site() -> &'static SiteConfig {
+	SITE.get_or_init(SiteConfig::default)
+}
+
+pub static BLOG: Lazy<&BlogMetadata> = Lazy::new(|| &site().blog);
+pub static EXTERNAL_LINKS: Lazy<&BTreeSet<External>> = Lazy::new(|| &site().external_links);
+pub static FEED: Lazy<&FeedConfig> = Lazy::new(|| &site().feed);
+pub static REDIRECTS: Lazy<&HashSet<RedirectPage>> = Lazy::new(|| &site().redirects);
+
+/// The full, public url of a page on the site
+pub fn canonicalize(path: &Path) -> Url {
+	BLOG
+		.canonical_origin
+		.join(path.to_str().expect("path contains invalid characters"))
+		.expect("failed to create canonical_url")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn write_config(name: &str, source: &str) -> PathBuf {
+		let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+		fs::write(&path, source).unwrap();
+		path
+	}
+
+	#[test]
+	fn loads_config() {
+		let path = write_config(
+			"site.toml",
+			r#"
+wat = true
+
+[blog]
+title = "Cool video games"
+titel = "Cool video games"
+
+[feed]
+content = "full"
+
+[[redirects]]
+from = "/old.html"
+to = "/new.html"
+permanent = true
+"#,
+		);
+
+		let mut diagnostics = Diagnostics::new(false);
+		let config = SiteConfig::load(Some(&path), &mut diagnostics);
+		assert!(diagnostics.errors.is_empty());
+		assert_eq!(config.blog.title, "Cool video games");
+		assert_eq!(config.blog.subtitle, BlogMetadata::default().subtitle);
+		assert_eq!(config.feed.content, FeedContent::Full);
+		assert_eq!(config.redirects.len(), 1);
+
+		let warnings = diagnostics
+			.warnings
+			.iter()
+			.map(|warning| warning.message.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			warnings,
+			[
+				"unknown site configuration field `wat`",
+				"unknown field `titel` in `[blog]`",
+				"unknown field `permanent` in redirect from `/old.html`",
+			]
+		);
+		assert_eq!(
+			diagnostics.warnings[1].hint.as_deref(),
+			Some("did you mean `title`?")
+		);
+
+		// The same keys fail the build when publishing
+		let mut diagnostics = Diagnostics::new(true);
+		SiteConfig::load(Some(&path), &mut diagnostics);
+		assert_eq!(diagnostics.errors.len(), 3);
+
+		fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn invalid_config_falls_back_to_defaults() {
+		let path = write_config(
+			"invalid.toml",
+			r#"
+[blog]
+title = "Cool video games"
+canonical_origin = "https://mckayla.blog/posts/"
+
+[[redirects]]
+from = "old.html"
+to = ""
+"#,
+		);
+
+		let mut diagnostics = Diagnostics::new(false);
+		let config = SiteConfig::load(Some(&path), &mut diagnostics);
+		let errors = diagnostics
+			.errors
+			.iter()
+			.map(|error| error.message.as_str())
+			.collect::<Vec<_>>();
+		assert_eq!(
+			errors,
+			[
+				"invalid canonical_origin `https://mckayla.blog/posts/`",
+				"redirect from `old.html` is not an absolute path",
+				"redirect from `old.html` doesn't go anywhere",
+			]
+		);
+		assert_eq!(config.blog.title, BlogMetadata::default().title);
+		assert_eq!(config.redirects, *DEFAULT_REDIRECTS);
+
+		fs::remove_file(&path).unwrap();
+	}
+
+	#[test]
+	fn unreadable_config_falls_back_to_defaults() {
+		let syntax = write_config("syntax.toml", "[blog\ntitle = 1\n");
+		let format = write_config("site.ini", "title = Cool video games\n");
+
+		for path in [syntax, format] {
+			let mut diagnostics = Diagnostics::new(false);
+			let config = SiteConfig::load(Some(&path), &mut diagnostics);
+			assert_eq!(diagnostics.errors.len(), 1);
+			assert_eq!(config.blog.title, BlogMetadata::default().title);
+			assert_eq!(config.feed.content, FeedContent::Summary);
+			fs::remove_file(&path).unwrap();
+		}
+	}
+}
@@ -1,9 +1,14 @@
 use chrono::NaiveDate;
+use chrono::NaiveTime;
 use serde::Deserialize;
 use serde::Serialize;
 use std::cmp::Ordering;
+use std::path::Path;
 
+use crate::index_entry::SortKey;
 use crate::pocky::de;
+use crate::pocky::fields::unknown_fields;
+use crate::pocky::fields::UnknownField;
 use crate::pocky::ser;
 
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
@@ -14,14 +19,39 @@ pub struct External {
 	pub metadata: ExternalMetadata,
 }
 
+impl External {
+	/// Finds any keys in an external link entry that don't correspond to a field.
+	/// `#[serde(flatten)]` hides the fields of `ExternalMetadata` from serde, so we have
+	/// to check those separately from our own.
+	pub fn unknown_fields<'k, K>(keys: K) -> Vec<UnknownField>
+	where
+		K: IntoIterator<Item = &'k str>,
+	{
+		let keys = keys
+			.into_iter()
+			.filter(|key| *key != "canonical_url" && *key != "path");
+		unknown_fields::<ExternalMetadata, _>(keys)
+	}
+
+	pub fn sort_key(&self) -> SortKey<'_> {
+		SortKey::new(
+			Some(&self.metadata.date),
+			self.metadata.time.as_ref(),
+			self.metadata.weight,
+			&self.metadata.title,
+			Path::new(&self.path),
+		)
+	}
+}
+
 impl PartialOrd for External {
 	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-		Some(self.metadata.date.cmp(&other.metadata.date).reverse())
+		Some(self.cmp(other))
 	}
 }
 impl Ord for External {
 	fn cmp(&self, other: &Self) -> Ordering {
-		self.metadata.date.cmp(&other.metadata.date).reverse()
+		self.sort_key().cmp(&other.sort_key())
 	}
 }
 
@@ -31,6 +61,11 @@ pub struct ExternalMetadata {
 	pub author: String,
 	#[serde(deserialize_with = "de::date", serialize_with = "ser::date_display")]
 	pub date: NaiveDate,
+	#[serde(default, deserialize_with = "de::time_option", skip_serializing)]
+	pub time: Option<NaiveTime>,
+	/// Orders entries that share a date and time, lowest first
+	#[serde(default)]
+	pub weight: i32,
 	#[serde(default, deserialize_with = "de::markdown_option")]
 	pub summary: Option<String>,
 	#[serde(default, deserialize_with = "de::comma_separated")]
@@ -0,0 +1,82 @@
+use chrono::SecondsFormat;
+use chrono::TimeZone;
+use chrono::Utc;
+use serde::Serialize;
+use url::Url;
+
+use crate::feed::Feed;
+use crate::feed::FeedEntry;
+
+/// A feed in the JSON Feed 1.1 format, as described at https://jsonfeed.org/version/1.1
+#[derive(Clone, Debug, Serialize)]
+struct JsonFeed<'a> {
+	version: &'static str,
+	title: &'a str,
+	home_page_url: &'a str,
+	feed_url: &'a str,
+	description: &'a str,
+	items: Vec<Item<'a>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Item<'a> {
+	id: &'a str,
+	url: &'a str,
+	title: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	content_html: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	content_text: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	summary: Option<&'a str>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	date_published: Option<String>,
+	authors: Vec<Author<'a>>,
+	#[serde(skip_serializing_if = "<[String]>::is_empty")]
+	tags: &'a [String],
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct Author<'a> {
+	name: &'a str,
+}
+
+impl<'a> From<&'a FeedEntry<'a>> for Item<'a> {
+	fn from(entry: &'a FeedEntry<'a>) -> Self {
+		Item {
+			id: entry.canonical_url,
+			url: entry.canonical_url,
+			title: entry.title,
+			content_html: entry.content.as_deref(),
+			// Every item needs some content, so fall back to the summary
+			content_text: match entry.content {
+				Some(_) => None,
+				None => Some(entry.summary.as_deref().unwrap_or_default()),
+			},
+			summary: entry.summary.as_deref(),
+			date_published: entry.date.map(|date| {
+				Utc
+					.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+					.to_rfc3339_opts(SecondsFormat::Secs, true)
+			}),
+			authors: vec![Author { name: entry.author }],
+			tags: entry.tags,
+		}
+	}
+}
+
+impl<'a> Feed<'a> {
+	/// Renders the feed as JSON Feed, to be hosted at `feed_url`
+	pub fn json(&self, feed_url: &Url) -> String {
+		let feed = JsonFeed {
+			version: "https://jsonfeed.org/version/1.1",
+			title: &self.title,
+			home_page_url: &self.url,
+			feed_url: feed_url.as_str(),
+			description: self.description,
+			items: self.entries.iter().map(Item::from).collect(),
+		};
+
+		serde_json::to_string_pretty(&feed).expect("failed to serialize feed")
+	}
+}
@@ -0,0 +1,92 @@
+use chrono::NaiveDate;
+use handlebars::Handlebars;
+use serde::Serialize;
+use serde_json::json;
+use url::Url;
+
+use crate::config::FeedContent;
+use crate::config::BLOG;
+use crate::config::FEED;
+use crate::index_entry::IndexEntry;
+
+mod json;
+mod rss;
+
+/// Everything that goes into a feed, independent of which format it's written in
+#[derive(Clone, Debug)]
+pub struct Feed<'a> {
+	pub title: String,
+	pub description: &'a str,
+	/// The page that the feed follows, like the index or a tag page
+	pub url: String,
+	pub entries: Vec<FeedEntry<'a>>,
+}
+
+/// A single entry in a feed. Posts, talks, and external links all have slightly
+/// different metadata, so they get flattened into this first.
+#[derive(Clone, Debug, Serialize)]
+pub struct FeedEntry<'a> {
+	pub title: &'a str,
+	pub author: &'a str,
+	#[serde(skip)]
+	pub date: Option<NaiveDate>,
+	pub updated: Option<String>,
+	/// A plain text summary of the entry
+	pub summary: Option<String>,
+	/// The rendered entry, if the feeds are configured to include it
+	pub content: Option<String>,
+	pub tags: &'a [String],
+	pub canonical_url: &'a str,
+	/// A recording of the entry, like the video of a talk
+	pub recording: Option<&'a str>,
+}
+
+impl<'a> From<&'a IndexEntry> for FeedEntry<'a> {
+	fn from(entry: &'a IndexEntry) -> Self {
+		FeedEntry {
+			title: entry.title(),
+			author: entry.author(),
+			date: entry.date().copied(),
+			updated: entry
+				.date()
+				.map(|date| date.format("%Y-%m-%dT00:00:00.000Z").to_string()),
+			summary: entry.description(),
+			content: match FEED.content {
+				FeedContent::Full => content(entry),
+				FeedContent::Summary => None,
+			},
+			tags: entry.tags(),
+			canonical_url: entry.canonical_url(),
+			recording: entry.recording(),
+		}
+	}
+}
+
+/// The html for the whole entry, with every link made absolute
+fn content(entry: &IndexEntry) -> Option<String> {
+	match entry {
+		IndexEntry::BlogPost(post) => Some(post.absolute_content()),
+		IndexEntry::Talk(talk) => Some(talk.absolute_content()),
+		IndexEntry::External(_) => None,
+	}
+}
+
+impl<'a> Feed<'a> {
+	/// Renders the feed as Atom, to be hosted at `feed_url`
+	pub fn atom(&self, feed_url: &Url) -> String {
+		Handlebars::new()
+			.render_template(
+				include_str!("../templates/feed.xml"),
+				&json!({
+					"blog": &*BLOG,
+					"feed": {
+						"title": &self.title,
+						"self_url": feed_url,
+						"url": &self.url,
+					},
+					"entries": &self.entries,
+				}),
+			)
+			.expect("failed to render handlebars")
+	}
+}
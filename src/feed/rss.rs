@@ -0,0 +1,146 @@
+use chrono::DateTime;
+use chrono::TimeZone;
+use chrono::Utc;
+use url::Url;
+
+use crate::feed::Feed;
+use crate::feed::FeedEntry;
+use crate::pocky::escape::escape;
+
+/// A feed in the RSS 2.0 format, as described at https://www.rssboard.org/rss-specification
+#[derive(Clone, Debug)]
+struct Channel<'a> {
+	title: &'a str,
+	link: &'a str,
+	description: &'a str,
+	feed_url: &'a str,
+	last_build_date: DateTime<Utc>,
+	items: Vec<Item<'a>>,
+}
+
+#[derive(Clone, Debug)]
+struct Item<'a> {
+	title: &'a str,
+	link: &'a str,
+	description: Option<&'a str>,
+	creator: &'a str,
+	pub_date: Option<DateTime<Utc>>,
+	categories: &'a [String],
+}
+
+impl<'a> From<&'a FeedEntry<'a>> for Item<'a> {
+	fn from(entry: &'a FeedEntry<'a>) -> Self {
+		Item {
+			title: entry.title,
+			link: entry.canonical_url,
+			description: entry.content.as_deref().or(entry.summary.as_deref()),
+			creator: entry.author,
+			pub_date: entry
+				.date
+				.map(|date| Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())),
+			categories: entry.tags,
+		}
+	}
+}
+
+/// Writes a single line element, escaping its text
+fn element(xml: &mut String, indent: usize, name: &str, text: &str) {
+	xml.push_str(&"\t".repeat(indent));
+	xml.push_str(&format!("<{}>{}</{}>\n", name, escape(text), name));
+}
+
+impl<'a> Channel<'a> {
+	fn to_xml(&self) -> String {
+		let mut xml = String::new();
+		xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+		xml.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n");
+		xml.push_str("\t<channel>\n");
+		element(&mut xml, 2, "title", self.title);
+		element(&mut xml, 2, "link", self.link);
+		element(&mut xml, 2, "description", self.description);
+		xml.push_str(&format!(
+			"\t\t<atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\" />\n",
+			escape(self.feed_url)
+		));
+		element(
+			&mut xml,
+			2,
+			"lastBuildDate",
+			&self.last_build_date.to_rfc2822(),
+		);
+
+		for item in self.items.iter() {
+			xml.push_str("\t\t<item>\n");
+			element(&mut xml, 3, "title", item.title);
+			element(&mut xml, 3, "link", item.link);
+			xml.push_str(&format!(
+				"\t\t\t<guid isPermaLink=\"true\">{}</guid>\n",
+				escape(item.link)
+			));
+			if let Some(description) = item.description {
+				element(&mut xml, 3, "description", description);
+			}
+			element(&mut xml, 3, "dc:creator", item.creator);
+			if let Some(pub_date) = item.pub_date {
+				element(&mut xml, 3, "pubDate", &pub_date.to_rfc2822());
+			}
+			for category in item.categories {
+				element(&mut xml, 3, "category", category);
+			}
+			xml.push_str("\t\t</item>\n");
+		}
+
+		xml.push_str("\t</channel>\n");
+		xml.push_str("</rss>\n");
+		xml
+	}
+}
+
+impl<'a> Feed<'a> {
+	/// Renders the feed as RSS, to be hosted at `feed_url`
+	pub fn rss(&self, feed_url: &Url) -> String {
+		let channel = Channel {
+			title: &self.title,
+			link: &self.url,
+			description: self.description,
+			feed_url: feed_url.as_str(),
+			last_build_date: Utc::now(),
+			items: self.entries.iter().map(Item::from).collect(),
+		};
+
+		channel.to_xml()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn escapes_everything() {
+		let tags = vec!["rust & friends".to_string()];
+		let feed = Feed {
+			title: "Kayla".to_string(),
+			description: "<3",
+			url: "https://mckayla.blog".to_string(),
+			entries: vec![FeedEntry {
+				title: "Cool \"video\" games",
+				author: "Kayla Washburn",
+				date: chrono::NaiveDate::from_ymd_opt(2023, 7, 15),
+				updated: None,
+				summary: Some("<p>games</p>".to_string()),
+				content: None,
+				tags: &tags,
+				canonical_url: "https://mckayla.blog/posts/cool-video-games.html",
+				recording: None,
+			}],
+		};
+		let xml = feed.rss(&Url::parse("https://mckayla.blog/rss.xml").unwrap());
+
+		assert!(xml.contains("<description>&lt;3</description>"));
+		assert!(xml.contains("<title>Cool &quot;video&quot; games</title>"));
+		assert!(xml.contains("<description>&lt;p&gt;games&lt;/p&gt;</description>"));
+		assert!(xml.contains("<category>rust &amp; friends</category>"));
+		assert!(xml.contains("<pubDate>Sat, 15 Jul 2023 00:00:00 +0000</pubDate>"));
+	}
+}
@@ -1,11 +1,57 @@
 use chrono::NaiveDate;
+use chrono::NaiveTime;
 use serde::Serialize;
 use std::cmp::Ordering;
+use std::cmp::Reverse;
+use std::path::Path;
 
 use crate::blog_post::BlogPost;
 use crate::external::External;
+use crate::pocky::md;
 use crate::talk::Talk;
 
+/// How entries are ordered wherever they're listed: newest first, then by `weight`,
+/// and finally by title and path, so that no two entries are ever considered equal
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct SortKey<'a> {
+	date: Reverse<Option<&'a NaiveDate>>,
+	time: Reverse<Option<&'a NaiveTime>>,
+	weight: i32,
+	title: &'a str,
+	path: &'a Path,
+}
+
+impl<'a> SortKey<'a> {
+	pub fn new(
+		date: Option<&'a NaiveDate>,
+		time: Option<&'a NaiveTime>,
+		weight: i32,
+		title: &'a str,
+		path: &'a Path,
+	) -> Self {
+		SortKey {
+			date: Reverse(date),
+			time: Reverse(time),
+			weight,
+			title,
+			path,
+		}
+	}
+
+	pub fn title(&self) -> &str {
+		self.title
+	}
+
+	/// Whether the order of two entries comes down to their titles, because nothing
+	/// else tells them apart
+	pub fn collides_with(&self, other: &SortKey) -> bool {
+		self.date.0.is_some()
+			&& self.date == other.date
+			&& self.time == other.time
+			&& self.weight == other.weight
+	}
+}
+
 #[derive(Clone, Debug, Serialize, Eq, PartialEq)]
 #[serde(untagged)]
 pub enum IndexEntry {
@@ -15,23 +61,95 @@ pub enum IndexEntry {
 }
 
 impl IndexEntry {
-	fn sort_criteria(&self) -> Option<&NaiveDate> {
+	pub fn title(&self) -> &str {
+		match self {
+			IndexEntry::BlogPost(blog_post) => &blog_post.metadata.title,
+			IndexEntry::External(external) => &external.metadata.title,
+			IndexEntry::Talk(talk) => &talk.metadata.title,
+		}
+	}
+
+	pub fn author(&self) -> &str {
+		match self {
+			IndexEntry::BlogPost(blog_post) => &blog_post.metadata.author,
+			IndexEntry::External(external) => &external.metadata.author,
+			IndexEntry::Talk(talk) => &talk.metadata.author,
+		}
+	}
+
+	pub fn date(&self) -> Option<&NaiveDate> {
 		match self {
 			IndexEntry::BlogPost(blog_post) => blog_post.metadata.date.as_ref(),
 			IndexEntry::External(external) => Some(&external.metadata.date),
 			IndexEntry::Talk(talk) => Some(&talk.metadata.date),
 		}
 	}
+
+	pub fn summary(&self) -> Option<&str> {
+		match self {
+			IndexEntry::BlogPost(blog_post) => blog_post.metadata.summary.as_deref(),
+			IndexEntry::External(external) => external.metadata.summary.as_deref(),
+			IndexEntry::Talk(talk) => talk.metadata.summary.as_deref(),
+		}
+	}
+
+	/// A plain text summary, for places that can't show html
+	pub fn description(&self) -> Option<String> {
+		match self {
+			IndexEntry::BlogPost(blog_post) => blog_post
+				.excerpt
+				.as_ref()
+				.map(|excerpt| excerpt.text.clone()),
+			_ => self.summary().map(md::html_to_text),
+		}
+	}
+
+	pub fn tags(&self) -> &[String] {
+		match self {
+			IndexEntry::BlogPost(blog_post) => &blog_post.metadata.tags,
+			IndexEntry::External(external) => &external.metadata.tags,
+			IndexEntry::Talk(talk) => &talk.metadata.tags,
+		}
+	}
+
+	pub fn canonical_url(&self) -> &str {
+		match self {
+			IndexEntry::BlogPost(blog_post) => blog_post.canonical_url.as_str(),
+			IndexEntry::External(external) => &external.canonical_url,
+			IndexEntry::Talk(talk) => talk.canonical_url.as_str(),
+		}
+	}
+
+	/// Whether the entry is a talk that hasn't been given yet
+	pub fn is_upcoming(&self) -> bool {
+		matches!(self, IndexEntry::Talk(talk) if talk.upcoming)
+	}
+
+	/// Where to watch the entry, if it was recorded
+	pub fn recording(&self) -> Option<&str> {
+		match self {
+			IndexEntry::Talk(talk) => talk.metadata.youtube.as_deref(),
+			_ => None,
+		}
+	}
+
+	pub fn sort_key(&self) -> SortKey<'_> {
+		match self {
+			IndexEntry::BlogPost(blog_post) => blog_post.sort_key(),
+			IndexEntry::External(external) => external.sort_key(),
+			IndexEntry::Talk(talk) => talk.sort_key(),
+		}
+	}
 }
 
 impl PartialOrd for IndexEntry {
 	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-		Some(self.sort_criteria().cmp(&other.sort_criteria()).reverse())
+		Some(self.cmp(other))
 	}
 }
 impl Ord for IndexEntry {
 	fn cmp(&self, other: &Self) -> Ordering {
-		self.sort_criteria().cmp(&other.sort_criteria()).reverse()
+		self.sort_key().cmp(&other.sort_key())
 	}
 }
 
@@ -52,3 +170,27 @@ impl From<Talk> for IndexEntry {
 		IndexEntry::Talk(talk)
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn sort_key_tie_breakers() {
+		let date = NaiveDate::from_ymd_opt(2023, 7, 15).unwrap();
+		let morning = NaiveTime::from_hms_opt(9, 30, 0).unwrap();
+		let path = Path::new("posts/cool-video-games.html");
+
+		let a = SortKey::new(Some(&date), None, 0, "A", path);
+		let b = SortKey::new(Some(&date), None, 0, "B", path);
+		assert!(a < b);
+		assert!(a.collides_with(&b));
+
+		let lighter = SortKey::new(Some(&date), None, -1, "B", path);
+		assert!(lighter < a);
+
+		let timed = SortKey::new(Some(&date), Some(&morning), 0, "B", path);
+		assert!(timed < a);
+		assert!(!timed.collides_with(&a));
+	}
+}
@@ -1,128 +1,165 @@
-use handlebars::Handlebars;
-use serde_json::json;
-use std::collections::BTreeSet;
 use std::env;
 use std::fs;
 use std::io;
+use std::path::Path;
+use std::process::exit;
+use std::thread;
 
 mod blog_post;
+mod build;
+mod calendar;
 mod config;
 mod external;
+mod feed;
 mod index_entry;
+mod meta;
+mod new;
 mod options;
 mod pocky;
+mod preview;
 mod redirect_page;
+mod search;
+mod serve;
+mod sitemap;
+mod slide;
+mod tags;
 mod talk;
+mod watch;
 
-use blog_post::BlogPost;
-use blog_post::BlogPostStatus::{Published, Unlisted};
-use config::BLOG;
-use config::EXTERNAL_LINKS;
-use config::REDIRECTS;
-use index_entry::IndexEntry;
+use build::Output;
+use build::Site;
+use config::SiteConfig;
+use options::Command;
 use options::Options;
-use pocky::pages_from_directory;
-use pocky::AsHtml;
-use talk::Talk;
+use pocky::Diagnostics;
+use serve::Reloader;
 
-fn main() -> io::Result<()> {
+fn main() {
 	let options = env::args().skip(1).collect::<Options>();
 
-	// Render redirects
-	for redirect in REDIRECTS.iter() {
-		let output_path = options.output.join(
-			redirect
-				.from
-				.strip_prefix("/")
-				.expect("`from` for redirect should be an absolute url"),
-		);
-		fs::create_dir_all(output_path.parent().unwrap()).expect("failed to create output directory");
-		fs::write(output_path, redirect.as_html())?;
-	}
+	let result = match &options.command {
+		Command::Build => build(&options),
+		Command::Serve { port } => serve(&options, *port),
+		Command::Check => check(&options),
+		Command::New {
+			title,
+			author,
+			talk,
+		} => new::new_page(title, author, *talk).map(|path| println!("created {}", path.display())),
+		Command::Clean => clean(&options),
+	};
 
-	// Collect posts into a `PageCollection`
-	let mut posts = pages_from_directory("content/posts/").collect::<Vec<BlogPost>>();
-	posts.iter_mut().for_each(|post| {
-		post.path = post.path.strip_prefix("content/").unwrap().to_owned();
-		post.canonicalize();
-	});
-	if options.publish {
-		// Skip unpublished posts if we're building a version for publishing
-		posts.retain(|post| post.metadata.status == Published || post.metadata.status == Unlisted);
-
-		// Ensure that all of the posts have a date
-		for post in posts.iter() {
-			if post.metadata.date.is_none() {
-				panic!("published posts must have a date");
-			}
-		}
+	if let Err(err) = result {
+		eprintln!("error: {}", err);
+		exit(1);
 	}
+}
 
-	// Render posts
-	for post in posts.iter() {
-		let output_path = options.output.join(&post.path);
-		fs::create_dir_all(output_path.parent().unwrap()).expect("failed to create output directory");
-		fs::write(output_path, post.as_html())?;
-	}
+fn load_site(options: &Options, diagnostics: &mut Diagnostics) -> Site {
+	config::init(SiteConfig::load(options.config.as_deref(), diagnostics));
+	Site::load(options, diagnostics)
+}
 
-	// Hide unlisted posts from the index and RSS feeds
-	posts.retain(|post| post.metadata.status != Unlisted);
+fn build(options: &Options) -> io::Result<()> {
+	let mut diagnostics = Diagnostics::new(options.publish);
+	let site = load_site(options, &mut diagnostics);
+	check_diagnostics(&diagnostics)?;
+	print_scheduled(&site);
+	site.render(&Output::new(&options.output))
+}
+
+fn serve(options: &Options, port: u16) -> io::Result<()> {
+	// Problems with individual pages shouldn't stop the server, since they'll
+	// hopefully be fixed while it's running
+	let mut diagnostics = Diagnostics::new(options.publish);
+	let mut site = load_site(options, &mut diagnostics);
+	print_diagnostics(&diagnostics);
+	print_scheduled(&site);
+	let output = Output::new(&options.output);
+	site.render(&output)?;
 
-	// Collect talks into a `PageCollection`
-	let mut talks = pages_from_directory("content/talks/").collect::<Vec<Talk>>();
-	talks.iter_mut().for_each(|talk| {
-		talk.path = talk.path.strip_prefix("content/").unwrap().to_owned();
+	let reloader = Reloader::default();
+	let watch_reloader = reloader.clone();
+	thread::spawn(move || {
+		watch::watch(&[Path::new("content/")], |changes| {
+			for change in changes.iter() {
+				println!("changed: {}", change.display());
+			}
+			let mut diagnostics = Diagnostics::new(site.publish);
+			match site.update(&changes, &output, &mut diagnostics) {
+				Ok(()) => watch_reloader.reload(),
+				Err(err) => eprintln!("error: failed to rebuild: {}", err),
+			}
+			print_diagnostics(&diagnostics);
+		})
 	});
 
-	// Render talks
-	for talk in talks.iter() {
-		let output_path = options.output.join(&talk.path);
-		fs::create_dir_all(output_path.parent().unwrap()).expect("failed to create output directory");
-		fs::write(output_path, talk.as_html())?;
+	serve::serve(&options.output, port, reloader)
+}
+
+fn check(options: &Options) -> io::Result<()> {
+	let mut diagnostics = Diagnostics::new(options.publish);
+	let site = load_site(options, &mut diagnostics);
+	check_diagnostics(&diagnostics)?;
+	print_scheduled(&site);
+	site.render(&Output::dry_run())?;
+	println!(
+		"checked {} posts and {} talks",
+		site.posts.len(),
+		site.talks.len()
+	);
+	Ok(())
+}
+
+fn clean(options: &Options) -> io::Result<()> {
+	match fs::remove_dir_all(&options.output) {
+		Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+		result => result,
 	}
+}
 
-	// Create the index entries set from posts and external links
-	let post_entries = posts.iter().cloned().map(Into::into);
-	let talk_entries = talks.iter().cloned().map(Into::into);
-	let external_link_entries = EXTERNAL_LINKS.iter().cloned().map(Into::into);
-	let index_entries = post_entries
-		.chain(talk_entries)
-		.chain(external_link_entries)
-		.collect::<BTreeSet<IndexEntry>>();
-
-	let renderer = Handlebars::new();
-	// Render index
-	let index_page = renderer
-		.render_template(
-			include_str!("./templates/index.html"),
-			&json!({ "blog": &*BLOG, "posts": &index_entries }),
-		)
-		.expect("failed to render handlebars");
-	fs::write(options.output.join("index.html"), index_page)?;
-	// Render feed.xml
-	let rss_feed = renderer
-		.render_template(
-			include_str!("./templates/feed.xml"),
-			&json!({ "blog": &*BLOG, "posts": &posts }),
-		)
-		.expect("failed to render handlebars");
-	fs::write(options.output.join("feed.xml"), rss_feed)?;
-
-	// Copy assets from content/resources/ to the output/resources/ directory
-	let output_path = options.output.join("resources/");
-	fs::create_dir_all(&output_path).expect("failed to create output directory");
-	for file in fs::read_dir("content/resources/")?
-		.flatten()
-		.map(|entry| entry.path())
-		.filter(|path| path.is_file())
-	{
-		fs::copy(
-			&file
-				.canonicalize()
-				.expect("failed to resolve file location"),
-			output_path.join(file.file_name().expect("failed to get file name")),
-		)?;
+/// Lists the posts that are being held back until their date
+fn print_scheduled(site: &Site) {
+	if site.scheduled.is_empty() {
+		return;
 	}
 
-	Ok(())
+	println!("scheduled after {}:", site.as_of);
+	for post in site.scheduled.iter() {
+		println!(
+			"  {}  {} (content/{})",
+			post
+				.metadata
+				.date
+				.expect("scheduled posts should have a date"),
+			post.metadata.title,
+			post.path.with_extension("md").display()
+		);
+	}
+	println!();
+}
+
+fn print_diagnostics(diagnostics: &Diagnostics) {
+	for warning in diagnostics.warnings.iter() {
+		eprintln!("warning: {}\n", warning);
+	}
+	for error in diagnostics.errors.iter() {
+		eprintln!("error: {}\n", error);
+	}
+}
+
+/// Reports every problem that we ran into while loading the site, and fails if any
+/// of them were errors
+fn check_diagnostics(diagnostics: &Diagnostics) -> io::Result<()> {
+	print_diagnostics(diagnostics);
+	match diagnostics.errors.len() {
+		0 => Ok(()),
+		1 => Err(io::Error::other(
+			"could not build the site due to the previous error",
+		)),
+		count => Err(io::Error::other(format!(
+			"could not build the site due to {} previous errors",
+			count
+		))),
+	}
 }
@@ -0,0 +1,217 @@
+use chrono::NaiveDate;
+use serde_json::json;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::blog_post::BlogPost;
+use crate::blog_post::BlogPostStatus::Unlisted;
+use crate::config;
+use crate::config::BLOG;
+use crate::pocky::escape::escape;
+use crate::pocky::md;
+use crate::preview;
+use crate::talk::Talk;
+
+/// Everything that link previews and search engines want to know about a page,
+/// rendered into the `<head>` as OpenGraph and Twitter Card tags, and JSON-LD.
+#[derive(Clone, Debug)]
+pub struct Meta {
+	pub kind: &'static str,
+	pub title: String,
+	pub description: String,
+	pub url: String,
+	pub image: String,
+	/// Only known for images that we generate ourselves
+	pub image_size: Option<(u32, u32)>,
+	pub author: Option<String>,
+	pub published: Option<NaiveDate>,
+	pub tags: Vec<String>,
+	pub json_ld: Option<Value>,
+	/// Keeps search engines from indexing the page, like for unlisted posts
+	pub noindex: bool,
+}
+
+/// Picks the best image out of a `cover` for link previews. Most sites that unfurl
+/// links don't support avif, so we only use it as a last resort.
+pub fn cover_image(cover: &Option<HashMap<String, String>>) -> Option<String> {
+	let cover = cover.as_ref()?;
+	["default", "jpeg", "webp", "avif"]
+		.iter()
+		.find_map(|format| cover.get(*format))
+		.cloned()
+}
+
+impl Meta {
+	/// Metadata for pages that aren't about any one thing, like the index
+	pub fn site(title: &str, url: &str) -> Self {
+		Meta {
+			kind: "website",
+			title: title.to_string(),
+			description: BLOG.subtitle.clone(),
+			url: url.to_string(),
+			image: BLOG.og_image.clone(),
+			image_size: None,
+			author: None,
+			published: None,
+			tags: Vec::new(),
+			json_ld: None,
+			noindex: false,
+		}
+	}
+
+	pub fn post(post: &BlogPost) -> Self {
+		let metadata = &post.metadata;
+		let description = post
+			.excerpt
+			.as_ref()
+			.map(|excerpt| excerpt.text.clone())
+			.unwrap_or_else(|| BLOG.subtitle.clone());
+		// Posts without a cover get a generated preview image instead
+		let (image, image_size) = match cover_image(&metadata.cover) {
+			Some(image) => (image, None),
+			None => (
+				config::canonicalize(&preview::preview_path(&post.path)).to_string(),
+				Some((preview::WIDTH, preview::HEIGHT)),
+			),
+		};
+
+		let json_ld = json!({
+			"@context": "https://schema.org",
+			"@type": "BlogPosting",
+			"headline": &metadata.title,
+			"description": &description,
+			"url": &post.canonical_url,
+			"mainEntityOfPage": &post.canonical_url,
+			"image": &image,
+			"datePublished": metadata.date.map(|date| date.to_string()),
+			"author": { "@type": "Person", "name": &metadata.author },
+			"keywords": &metadata.tags,
+		});
+
+		Meta {
+			kind: "article",
+			title: metadata.title.clone(),
+			description,
+			url: post.canonical_url.to_string(),
+			image,
+			image_size,
+			author: Some(metadata.author.clone()),
+			published: metadata.date,
+			tags: metadata.tags.clone(),
+			json_ld: Some(json_ld),
+			noindex: metadata.status == Unlisted,
+		}
+	}
+
+	pub fn talk(talk: &Talk) -> Self {
+		let metadata = &talk.metadata;
+		let description = metadata
+			.summary
+			.as_deref()
+			.map(md::html_to_text)
+			.unwrap_or_else(|| BLOG.subtitle.clone());
+		let image = cover_image(&metadata.cover).unwrap_or_else(|| BLOG.og_image.clone());
+
+		let json_ld = json!({
+			"@context": "https://schema.org",
+			"@type": "Event",
+			"name": &metadata.title,
+			"description": &description,
+			"url": &talk.canonical_url,
+			"image": &image,
+			"startDate": metadata.date.to_string(),
+			"performer": { "@type": "Person", "name": &metadata.author },
+			"keywords": &metadata.tags,
+		});
+
+		Meta {
+			kind: "article",
+			title: metadata.title.clone(),
+			description,
+			url: talk.canonical_url.to_string(),
+			image,
+			image_size: None,
+			author: Some(metadata.author.clone()),
+			published: Some(metadata.date),
+			tags: metadata.tags.clone(),
+			json_ld: Some(json_ld),
+			noindex: false,
+		}
+	}
+
+	/// Renders all of the tags that belong in the `<head>` of the page
+	pub fn to_html(&self) -> String {
+		let mut tags = vec![
+			("property", "og:type", self.kind.to_string()),
+			("property", "og:site_name", BLOG.og_title.clone()),
+			("property", "og:title", self.title.clone()),
+			("property", "og:description", self.description.clone()),
+			("property", "og:url", self.url.clone()),
+			("property", "og:image", self.image.clone()),
+		];
+		if let Some((width, height)) = self.image_size {
+			tags.push(("property", "og:image:width", width.to_string()));
+			tags.push(("property", "og:image:height", height.to_string()));
+		}
+		if let Some(published) = self.published {
+			tags.push(("property", "article:published_time", published.to_string()));
+		}
+		if let Some(author) = &self.author {
+			tags.push(("property", "article:author", author.clone()));
+		}
+		for tag in self.tags.iter() {
+			tags.push(("property", "article:tag", tag.clone()));
+		}
+		tags.extend([
+			("name", "twitter:card", "summary_large_image".to_string()),
+			("name", "twitter:title", self.title.clone()),
+			("name", "twitter:description", self.description.clone()),
+			("name", "twitter:image", self.image.clone()),
+		]);
+
+		let mut html = format!(
+			"<link rel=\"canonical\" href=\"{}\" />\n",
+			escape(&self.url)
+		);
+		if self.noindex {
+			html.push_str("<meta name=\"robots\" content=\"noindex\" />\n");
+		}
+		for (attribute, name, content) in tags {
+			html.push_str(&format!(
+				"<meta {}=\"{}\" content=\"{}\" />\n",
+				attribute,
+				name,
+				escape(&content)
+			));
+		}
+
+		if let Some(json_ld) = &self.json_ld {
+			// `</script>` inside of a string would still end the script early
+			let json_ld = json_ld.to_string().replace("</", "<\\/");
+			html.push_str(&format!(
+				"<script type=\"application/ld+json\">{}</script>\n",
+				json_ld
+			));
+		}
+
+		html
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn escapes_content() {
+		let mut meta = Meta::site("Cool \"video\" games", "https://mckayla.blog/");
+		meta.json_ld = Some(json!({ "name": "</script>" }));
+		let html = meta.to_html();
+
+		assert!(
+			html.contains("<meta property=\"og:title\" content=\"Cool &quot;video&quot; games\" />")
+		);
+		assert!(html.contains("<meta name=\"twitter:card\" content=\"summary_large_image\" />"));
+		assert!(html.contains("{\"name\":\"<\\/script>\"}"));
+	}
+}
@@ -0,0 +1,92 @@
+use chrono::Local;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::pocky::slug::slugify;
+
+/// The frontmatter that new pages start with
+#[derive(Serialize)]
+struct NewPage<'a> {
+	title: &'a str,
+	author: &'a str,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	status: Option<&'a str>,
+	date: String,
+}
+
+/// Creates a new draft post (or talk) with all of the frontmatter filled in
+pub fn new_page(title: &str, author: &str, talk: bool) -> io::Result<PathBuf> {
+	let slug = slugify(title);
+	if slug.is_empty() {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidInput,
+			"title must contain at least one letter or number",
+		));
+	}
+
+	let directory = if talk {
+		"content/talks/"
+	} else {
+		"content/posts/"
+	};
+	let path = PathBuf::from(directory).join(format!("{}.md", slug));
+	let source = source(&NewPage {
+		title,
+		author,
+		// Talks don't have a status
+		status: (!talk).then_some("draft"),
+		date: Local::now().format("%Y.%-m.%-d").to_string(),
+	})?;
+
+	fs::create_dir_all(directory)?;
+	// `create_new` makes sure that we never clobber an existing post
+	fs::OpenOptions::new()
+		.write(true)
+		.create_new(true)
+		.open(&path)
+		.and_then(|mut file| io::Write::write_all(&mut file, source.as_bytes()))
+		.map_err(|err| match err.kind() {
+			io::ErrorKind::AlreadyExists => {
+				io::Error::new(err.kind(), format!("{} already exists", path.display()))
+			}
+			_ => err,
+		})?;
+
+	Ok(path)
+}
+
+/// Titles and names can contain anything, so the frontmatter is serialized rather than
+/// formatted, to make sure that it's always valid YAML
+fn source(page: &NewPage) -> io::Result<String> {
+	let frontmatter =
+		serde_yaml::to_string(page).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+	Ok(format!("---\n{}---\n\n", frontmatter))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::talk::TalkMetadata;
+
+	#[test]
+	fn escapes_frontmatter() {
+		let source = source(&NewPage {
+			title: "Cool video games: a retrospective",
+			author: "Kayla \"Aslilac\" Washburn",
+			status: None,
+			date: "2023.7.15".to_string(),
+		})
+		.unwrap();
+
+		let frontmatter = source
+			.strip_prefix("---\n")
+			.and_then(|source| source.strip_suffix("---\n\n"))
+			.unwrap();
+		let metadata = serde_yaml::from_str::<TalkMetadata>(frontmatter).unwrap();
+		assert_eq!(metadata.title, "Cool video games: a retrospective");
+		assert_eq!(metadata.author, "Kayla \"Aslilac\" Washburn");
+		assert_eq!(metadata.date.to_string(), "2023-07-15");
+	}
+}
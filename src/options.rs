@@ -1,27 +1,173 @@
+use chrono::NaiveDate;
 use std::path::PathBuf;
 use std::process::exit;
 
+const USAGE: &str = "\
+usage: mckayla_blog [command] [options]
+
+commands:
+  build    render the site into the output directory (default)
+  serve    build the site and host it over local http
+  check    render the site without writing anything, and report problems
+  new      create a new draft post or talk
+  clean    remove the output directory
+
+run `mckayla_blog <command> --help` for more information about a command";
+
+const BUILD_USAGE: &str = "\
+usage: mckayla_blog build [output] [options]
+
+options:
+  -o, --output <dir>  where to write the rendered site (default: ./output/)
+  -p, --publish       only include published and unlisted posts that are due
+      --as-of <date>  publish as if it were the given day, like 2023-07-15
+      --config <file> the site configuration to use (default: ./site.toml)
+  -h, --help          show this message";
+
+const SERVE_USAGE: &str = "\
+usage: mckayla_blog serve [options]
+
+options:
+  -o, --output <dir>  where to write the rendered site (default: ./output/)
+  -p, --publish       only include published and unlisted posts that are due
+      --as-of <date>  publish as if it were the given day, like 2023-07-15
+      --port <port>   the port to listen on (default: 8080)
+      --config <file> the site configuration to use (default: ./site.toml)
+  -h, --help          show this message";
+
+const CHECK_USAGE: &str = "\
+usage: mckayla_blog check [options]
+
+options:
+  -p, --publish       check the site as it would be published
+      --as-of <date>  publish as if it were the given day, like 2023-07-15
+      --config <file> the site configuration to use (default: ./site.toml)
+  -h, --help          show this message";
+
+const NEW_USAGE: &str = "\
+usage: mckayla_blog new <title> [options]
+
+options:
+      --talk          create a talk instead of a blog post
+      --author <name> who the post is by (default: Kayla Washburn)
+  -h, --help          show this message";
+
+const CLEAN_USAGE: &str = "\
+usage: mckayla_blog clean [options]
+
+options:
+  -o, --output <dir>  the output directory to remove (default: ./output/)
+  -h, --help          show this message";
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CommandKind {
+	#[default]
+	Build,
+	Serve,
+	Check,
+	New,
+	Clean,
+}
+
+impl CommandKind {
+	fn from_name(name: &str) -> Option<Self> {
+		match name {
+			"build" => Some(CommandKind::Build),
+			"serve" => Some(CommandKind::Serve),
+			"check" => Some(CommandKind::Check),
+			"new" => Some(CommandKind::New),
+			"clean" => Some(CommandKind::Clean),
+			_ => None,
+		}
+	}
+
+	fn usage(&self) -> &'static str {
+		match self {
+			CommandKind::Build => BUILD_USAGE,
+			CommandKind::Serve => SERVE_USAGE,
+			CommandKind::Check => CHECK_USAGE,
+			CommandKind::New => NEW_USAGE,
+			CommandKind::Clean => CLEAN_USAGE,
+		}
+	}
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Command {
+	Build,
+	Serve {
+		port: u16,
+	},
+	Check,
+	New {
+		title: String,
+		author: String,
+		talk: bool,
+	},
+	Clean,
+}
+
 #[derive(Clone, Debug, Default)]
 struct OptionsBuilder {
+	command: Option<CommandKind>,
 	output: Option<PathBuf>,
 	publish: bool,
+	as_of: Option<NaiveDate>,
+	config: Option<PathBuf>,
+	port: Option<u16>,
+	title: Option<String>,
+	author: Option<String>,
+	talk: bool,
 }
 
 #[derive(Clone, Debug)]
 pub struct Options {
+	pub command: Command,
 	pub output: PathBuf,
 	pub publish: bool,
+	/// The day that scheduled posts are judged against, if not today
+	pub as_of: Option<NaiveDate>,
+	pub config: Option<PathBuf>,
 }
 
 impl From<OptionsBuilder> for Options {
 	fn from(builder: OptionsBuilder) -> Self {
+		let command = match builder.command.unwrap_or_default() {
+			CommandKind::Build => Command::Build,
+			CommandKind::Serve => Command::Serve {
+				port: builder.port.unwrap_or(8080),
+			},
+			CommandKind::Check => Command::Check,
+			CommandKind::New => Command::New {
+				title: builder
+					.title
+					.unwrap_or_else(|| usage_error(CommandKind::New, "missing a title for the new page")),
+				author: builder
+					.author
+					.unwrap_or_else(|| "Kayla Washburn".to_string()),
+				talk: builder.talk,
+			},
+			CommandKind::Clean => Command::Clean,
+		};
+
 		Options {
+			command,
 			output: builder.output.unwrap_or_else(|| PathBuf::from("./output/")),
-			publish: builder.publish,
+			// Previewing a future day only makes sense for the published site
+			publish: builder.publish || builder.as_of.is_some(),
+			as_of: builder.as_of,
+			config: builder.config,
 		}
 	}
 }
 
+/// Prints an error along with the usage of the given command, and exits with the
+/// conventional "bad usage" exit code.
+fn usage_error(command: CommandKind, message: &str) -> ! {
+	eprintln!("error: {}\n\n{}", message, command.usage());
+	exit(2);
+}
+
 impl<S> FromIterator<S> for Options
 where
 	S: AsRef<str>,
@@ -35,21 +181,141 @@ where
 
 		while let Some(arg) = args.next() {
 			let arg = arg.as_ref();
+			let command = options.command.unwrap_or_default();
+
 			if (arg.len() >= 2 && arg.starts_with('-')) || arg.len() >= 3 && arg.starts_with("--") {
-				match arg {
-					"-p" | "-pub" | "--pub" | "-publish" | "--publish" => {
+				let mut value = |name: &str| {
+					args
+						.next()
+						.map(|value| value.as_ref().to_string())
+						.unwrap_or_else(|| usage_error(command, &format!("{} expects a value", name)))
+				};
+
+				match (command, arg) {
+					(_, "-h" | "-help" | "--help") => {
+						match options.command {
+							Some(command) => println!("{}", command.usage()),
+							None => println!("{}", USAGE),
+						}
+						exit(0);
+					}
+					(
+						CommandKind::Build | CommandKind::Serve | CommandKind::Check,
+						"-p" | "-pub" | "--pub" | "-publish" | "--publish",
+					) => {
 						options.publish = true;
 					}
-					_ => {
-						println!("unrecognized option: {}", arg);
-						exit(1);
+					(CommandKind::Build | CommandKind::Serve | CommandKind::Check, "-as-of" | "--as-of") => {
+						let date = value(arg);
+						options.as_of = Some(NaiveDate::parse_from_str(&date, "%Y-%m-%d").unwrap_or_else(
+							|_| {
+								usage_error(
+									command,
+									&format!("invalid date: {}, expected something like 2023-07-15", date),
+								)
+							},
+						));
+					}
+					(
+						CommandKind::Build | CommandKind::Serve | CommandKind::Clean,
+						"-o" | "-output" | "--output",
+					) => {
+						options.output = Some(PathBuf::from(value(arg)));
+					}
+					(
+						CommandKind::Build | CommandKind::Serve | CommandKind::Check,
+						"-config" | "--config",
+					) => {
+						options.config = Some(PathBuf::from(value(arg)));
+					}
+					(CommandKind::Serve, "--port") => {
+						let port = value(arg);
+						options.port = Some(
+							port
+								.parse()
+								.unwrap_or_else(|_| usage_error(command, &format!("invalid port: {}", port))),
+						);
 					}
+					(CommandKind::New, "--author") => {
+						options.author = Some(value(arg));
+					}
+					(CommandKind::New, "--talk") => {
+						options.talk = true;
+					}
+					_ => usage_error(command, &format!("unrecognized option: {}", arg)),
 				}
+			} else if options.command.is_none() && CommandKind::from_name(arg).is_some() {
+				options.command = CommandKind::from_name(arg);
 			} else {
-				options.output = Some(PathBuf::from(arg));
+				match command {
+					// For backwards compatibility, `build` still accepts the output
+					// directory as a positional argument.
+					CommandKind::Build if options.output.is_none() => {
+						options.output = Some(PathBuf::from(arg));
+					}
+					CommandKind::New if options.title.is_none() => {
+						options.title = Some(arg.to_string());
+					}
+					_ => usage_error(command, &format!("unexpected argument: {}", arg)),
+				}
+				options.command.get_or_insert(command);
 			}
 		}
 
 		options.into()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn defaults_to_build() {
+		let options = Vec::<&str>::new().into_iter().collect::<Options>();
+
+		assert_eq!(options.command, Command::Build);
+		assert_eq!(options.output, PathBuf::from("./output/"));
+		assert!(!options.publish);
+	}
+
+	#[test]
+	fn legacy_arguments() {
+		let options = ["./public/", "-publish"].into_iter().collect::<Options>();
+
+		assert_eq!(options.command, Command::Build);
+		assert_eq!(options.output, PathBuf::from("./public/"));
+		assert!(options.publish);
+	}
+
+	#[test]
+	fn subcommands() {
+		let options = ["serve", "--port", "3000", "-p"]
+			.into_iter()
+			.collect::<Options>();
+		assert_eq!(options.command, Command::Serve { port: 3000 });
+		assert!(options.publish);
+
+		let options = ["new", "Cool video games", "--talk"]
+			.into_iter()
+			.collect::<Options>();
+		assert_eq!(
+			options.command,
+			Command::New {
+				title: "Cool video games".to_string(),
+				author: "Kayla Washburn".to_string(),
+				talk: true,
+			}
+		);
+	}
+
+	#[test]
+	fn as_of_implies_publish() {
+		let options = ["check", "--as-of", "2023-07-15"]
+			.into_iter()
+			.collect::<Options>();
+		assert_eq!(options.command, Command::Check);
+		assert_eq!(options.as_of, NaiveDate::from_ymd_opt(2023, 7, 15));
+		assert!(options.publish);
+	}
+}
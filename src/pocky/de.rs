@@ -1,17 +1,29 @@
 use chrono::NaiveDate;
+use chrono::NaiveTime;
+use serde::de::Error;
 use serde::Deserialize;
 use serde::Deserializer;
+use toml::value::Datetime;
 
 use crate::pocky::md;
 
+/// Lists can be written either as a comma separated string, or as an actual list,
+/// which is what other static site generators tend to use
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CommaSeparated {
+	String(String),
+	List(Vec<String>),
+}
+
 pub fn comma_separated<'de, D>(de: D) -> Result<Vec<String>, D::Error>
 where
 	D: Deserializer<'de>,
 {
-	let tags = String::deserialize(de)?
-		.split(',')
-		.map(|tag| tag.trim().to_string())
-		.collect();
+	let tags = match CommaSeparated::deserialize(de)? {
+		CommaSeparated::String(tags) => tags.split(',').map(|tag| tag.trim().to_string()).collect(),
+		CommaSeparated::List(tags) => tags.into_iter().map(|tag| tag.trim().to_string()).collect(),
+	};
 
 	Ok(tags)
 }
@@ -31,25 +43,112 @@ where
 	Ok(Option::<String>::deserialize(de)?.map(md::markdown_to_html))
 }
 
+/// The format that we write dates in, like `2023.7.15`
+const DATE_FORMAT: &str = "%Y.%m.%d";
+
+/// Dates (and times) can be a string, or a native TOML datetime
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DateSource {
+	String(String),
+	Toml(Datetime),
+}
+
+impl DateSource {
+	fn into_date<E>(self) -> Result<NaiveDate, E>
+	where
+		E: Error,
+	{
+		let date_string = match self {
+			DateSource::String(string) => string,
+			DateSource::Toml(datetime) => {
+				// Any time of day is ignored, just like for strings
+				return datetime
+					.date
+					.and_then(|date| {
+						NaiveDate::from_ymd_opt(date.year.into(), date.month.into(), date.day.into())
+					})
+					.ok_or_else(|| {
+						E::custom(format!(
+							"invalid date `{}`, expected something like `2023-07-15`",
+							datetime
+						))
+					});
+			}
+		};
+
+		// We also accept ISO 8601 dates, ignoring any time of day, since that's how
+		// imported posts will usually have them
+		let date_part = date_string.split(['T', ' ']).next().unwrap_or_default();
+		NaiveDate::parse_from_str(&date_string, DATE_FORMAT)
+			.or_else(|_| NaiveDate::parse_from_str(date_part, "%Y-%m-%d"))
+			.map_err(|_| {
+				E::custom(format!(
+					"invalid date `{}`, expected something like `2023.7.15`",
+					date_string
+				))
+			})
+	}
+
+	/// Times of day are only used to order things that happen on the same date, like
+	/// `14:30`, or `14:30:00`
+	fn into_time<E>(self) -> Result<NaiveTime, E>
+	where
+		E: Error,
+	{
+		let time_string = match self {
+			DateSource::String(string) => string,
+			DateSource::Toml(datetime) => {
+				return datetime
+					.time
+					.and_then(|time| {
+						NaiveTime::from_hms_nano_opt(
+							time.hour.into(),
+							time.minute.into(),
+							time.second.into(),
+							time.nanosecond,
+						)
+					})
+					.ok_or_else(|| {
+						E::custom(format!(
+							"invalid time `{}`, expected something like `14:30:00`",
+							datetime
+						))
+					});
+			}
+		};
+		NaiveTime::parse_from_str(&time_string, "%H:%M")
+			.or_else(|_| NaiveTime::parse_from_str(&time_string, "%H:%M:%S"))
+			.map_err(|_| {
+				E::custom(format!(
+					"invalid time `{}`, expected something like `14:30`",
+					time_string
+				))
+			})
+	}
+}
+
 pub fn date<'de, D>(de: D) -> Result<NaiveDate, D::Error>
 where
 	D: Deserializer<'de>,
 {
-	let date_string = String::deserialize(de)?;
-	let date = NaiveDate::parse_from_str(&date_string, "%Y.%m.%d")
-		.unwrap_or_else(|_| panic!("invalid date: {}", date_string));
-
-	Ok(date)
+	DateSource::deserialize(de)?.into_date()
 }
 
 pub fn date_option<'de, D>(de: D) -> Result<Option<NaiveDate>, D::Error>
 where
 	D: Deserializer<'de>,
 {
-	let date = Option::<String>::deserialize(de)?.map(|date_string| {
-		NaiveDate::parse_from_str(&date_string, "%Y.%m.%d")
-			.unwrap_or_else(|_| panic!("invalid date: {}", date_string))
-	});
+	Option::<DateSource>::deserialize(de)?
+		.map(DateSource::into_date)
+		.transpose()
+}
 
-	Ok(date)
+pub fn time_option<'de, D>(de: D) -> Result<Option<NaiveTime>, D::Error>
+where
+	D: Deserializer<'de>,
+{
+	Option::<DateSource>::deserialize(de)?
+		.map(DateSource::into_time)
+		.transpose()
 }
@@ -0,0 +1,126 @@
+use std::error::Error;
+use std::fmt;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// A problem with one of the source files that stops it from being built, along with
+/// enough context to go and fix it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BuildError {
+	pub path: PathBuf,
+	pub line: Option<usize>,
+	pub column: Option<usize>,
+	pub message: String,
+	pub hint: Option<String>,
+}
+
+impl BuildError {
+	pub fn new<M>(message: M) -> Self
+	where
+		M: Into<String>,
+	{
+		BuildError {
+			path: PathBuf::new(),
+			line: None,
+			column: None,
+			message: message.into(),
+			hint: None,
+		}
+	}
+
+	pub fn in_file<P>(mut self, path: P) -> Self
+	where
+		P: AsRef<Path>,
+	{
+		self.path = path.as_ref().to_owned();
+		self
+	}
+
+	pub fn at(mut self, line: usize, column: usize) -> Self {
+		self.line = Some(line);
+		self.column = Some(column);
+		self
+	}
+
+	pub fn with_hint<H>(mut self, hint: H) -> Self
+	where
+		H: Into<String>,
+	{
+		self.hint = Some(hint.into());
+		self
+	}
+}
+
+impl Display for BuildError {
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		write!(f, "{}", self.message)?;
+		if !self.path.as_os_str().is_empty() {
+			write!(f, "\n  --> {}", self.path.display())?;
+			if let (Some(line), Some(column)) = (self.line, self.column) {
+				write!(f, ":{}:{}", line, column)?;
+			}
+		}
+		if let Some(hint) = &self.hint {
+			write!(f, "\n  = hint: {}", hint)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Error for BuildError {}
+
+/// Everything that went wrong during a build. In strict mode, which we use when
+/// publishing, warnings are treated as errors.
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+	pub strict: bool,
+	pub errors: Vec<BuildError>,
+	pub warnings: Vec<BuildError>,
+}
+
+impl Diagnostics {
+	pub fn new(strict: bool) -> Self {
+		Diagnostics {
+			strict,
+			..Default::default()
+		}
+	}
+
+	pub fn error(&mut self, error: BuildError) {
+		self.errors.push(error);
+	}
+
+	pub fn warn(&mut self, warning: BuildError) {
+		match self.strict {
+			true => self.errors.push(warning),
+			false => self.warnings.push(warning),
+		}
+	}
+
+	/// Reports something that's worth knowing about, but isn't a problem with the
+	/// content, so unlike a warning it never fails a strict build
+	pub fn note(&mut self, note: BuildError) {
+		self.warnings.push(note);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn display() {
+		let error = BuildError::new("invalid date: tomorrow")
+			.in_file("content/posts/hello.md")
+			.at(4, 7)
+			.with_hint("dates should look like 2023.7.15");
+
+		assert_eq!(
+			error.to_string(),
+			"invalid date: tomorrow\n  --> content/posts/hello.md:4:7\n  = hint: dates should look like 2023.7.15"
+		);
+	}
+}
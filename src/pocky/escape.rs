@@ -0,0 +1,10 @@
+/// Escapes text so that it can be used as the content of an element, or as the value
+/// of a quoted attribute, in either html or xml
+pub fn escape(text: &str) -> String {
+	text
+		.replace('&', "&amp;")
+		.replace('<', "&lt;")
+		.replace('>', "&gt;")
+		.replace('"', "&quot;")
+		.replace('\'', "&apos;")
+}
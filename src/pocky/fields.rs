@@ -0,0 +1,178 @@
+use serde::de::Error;
+use serde::de::Visitor;
+use serde::forward_to_deserialize_any;
+use serde::Deserialize;
+use serde::Deserializer;
+
+/// Names that other static site generators use for things that we call something
+/// else, so that imported posts get a helpful suggestion.
+const SYNONYMS: &[(&str, &str)] = &[
+	("description", "summary"),
+	("excerpt", "summary"),
+	("categories", "tags"),
+	("keywords", "tags"),
+	("tag", "tags"),
+	("image", "cover"),
+	("draft", "status"),
+	("published", "date"),
+	("pubdate", "date"),
+	("color", "accent_color"),
+	("authors", "author"),
+];
+
+/// A frontmatter key that doesn't correspond to any field, and would otherwise be
+/// silently dropped
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UnknownField {
+	pub key: String,
+	pub suggestion: Option<&'static str>,
+}
+
+impl UnknownField {
+	/// Describes the key as a field of `name`, like "frontmatter"
+	pub fn message(&self, name: &str) -> String {
+		format!("unknown {} field `{}`", name, self.key)
+	}
+
+	pub fn hint(&self) -> Option<String> {
+		self
+			.suggestion
+			.map(|suggestion| format!("did you mean `{}`?", suggestion))
+	}
+}
+
+/// Lists the fields that a struct knows about, by pretending to be a deserializer and
+/// recording what the derived implementation asks for. Returns an empty list for
+/// anything that isn't a plain struct, like maps or structs with flattened fields.
+pub fn field_names<'de, T>() -> &'static [&'static str]
+where
+	T: Deserialize<'de>,
+{
+	struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+	impl<'de, 'a> Deserializer<'de> for FieldNames<'a> {
+		type Error = serde::de::value::Error;
+
+		fn deserialize_any<V>(self, _: V) -> Result<V::Value, Self::Error>
+		where
+			V: Visitor<'de>,
+		{
+			Err(Self::Error::custom("not a struct"))
+		}
+
+		fn deserialize_struct<V>(
+			self,
+			_: &'static str,
+			fields: &'static [&'static str],
+			_: V,
+		) -> Result<V::Value, Self::Error>
+		where
+			V: Visitor<'de>,
+		{
+			*self.0 = fields;
+			Err(Self::Error::custom("only collecting field names"))
+		}
+
+		forward_to_deserialize_any! {
+			bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+			byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map
+			enum identifier ignored_any
+		}
+	}
+
+	let mut fields: &'static [&'static str] = &[];
+	let _ = T::deserialize(FieldNames(&mut fields));
+	fields
+}
+
+/// Compares the given keys against the fields of `T`, and returns the ones that `T`
+/// doesn't know about, along with the closest real field name, if there is one.
+pub fn unknown_fields<'de, 'k, T, K>(keys: K) -> Vec<UnknownField>
+where
+	T: Deserialize<'de>,
+	K: IntoIterator<Item = &'k str>,
+{
+	let fields = field_names::<T>();
+	if fields.is_empty() {
+		return Vec::new();
+	}
+
+	keys
+		.into_iter()
+		.filter(|key| !fields.contains(key))
+		.map(|key| UnknownField {
+			key: key.to_string(),
+			suggestion: suggest(key, fields),
+		})
+		.collect()
+}
+
+fn suggest(key: &str, fields: &[&'static str]) -> Option<&'static str> {
+	let normalized = key.to_lowercase().replace('-', "_");
+
+	let synonym = SYNONYMS
+		.iter()
+		.filter(|(synonym, _)| *synonym == normalized)
+		.find_map(|(_, field)| fields.iter().find(|it| *it == field));
+	if let Some(field) = synonym {
+		return Some(field);
+	}
+
+	fields
+		.iter()
+		.map(|field| (strsim::levenshtein(&normalized, field), *field))
+		.filter(|(distance, field)| *distance <= (field.len() / 3).max(2))
+		.min()
+		.map(|(_, field)| field)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+
+	#[derive(Deserialize)]
+	#[allow(dead_code)]
+	struct Metadata {
+		title: String,
+		summary: Option<String>,
+		accent_color: Option<String>,
+	}
+
+	#[test]
+	fn lists_field_names() {
+		assert_eq!(
+			field_names::<Metadata>(),
+			&["title", "summary", "accent_color"]
+		);
+		assert!(field_names::<HashMap<String, String>>().is_empty());
+	}
+
+	#[test]
+	fn suggests_fields() {
+		let unknown =
+			unknown_fields::<Metadata, _>(["title", "description", "titel", "accent-color", "wat"]);
+
+		assert_eq!(
+			unknown,
+			vec![
+				UnknownField {
+					key: "description".to_string(),
+					suggestion: Some("summary"),
+				},
+				UnknownField {
+					key: "titel".to_string(),
+					suggestion: Some("title"),
+				},
+				UnknownField {
+					key: "accent-color".to_string(),
+					suggestion: Some("accent_color"),
+				},
+				UnknownField {
+					key: "wat".to_string(),
+					suggestion: None,
+				},
+			]
+		);
+	}
+}
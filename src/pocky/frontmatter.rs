@@ -0,0 +1,331 @@
+use serde::de::DeserializeOwned;
+use serde::de::IgnoredAny;
+use serde_path_to_error::Segment;
+
+use crate::pocky::fields::unknown_fields;
+use crate::pocky::BuildError;
+
+/// The formats that frontmatter can be written in, chosen by how the block starts:
+/// `---` for YAML, `+++` for TOML (like Hugo and Zola), and `{` for JSON.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+	Yaml,
+	Toml,
+	Json,
+}
+
+impl Format {
+	fn name(&self) -> &'static str {
+		match self {
+			Format::Yaml => "YAML",
+			Format::Toml => "TOML",
+			Format::Json => "JSON",
+		}
+	}
+
+	/// If the given line sets the given top level key, returns the column where the
+	/// key starts, and the column where its value starts
+	fn key_columns(&self, line: &str, key: &str) -> Option<(usize, usize)> {
+		let indent = line.len() - line.trim_start().len();
+		let value = match self {
+			Format::Yaml => line.strip_prefix(key)?.strip_prefix(':')?,
+			Format::Toml => line[indent..]
+				.strip_prefix(key)?
+				.trim_start()
+				.strip_prefix('=')?,
+			Format::Json => line[indent..]
+				.strip_prefix('"')?
+				.strip_prefix(key)?
+				.strip_prefix('"')?
+				.trim_start()
+				.strip_prefix(':')?,
+		};
+
+		Some((indent + 1, line.len() - value.trim_start().len() + 1))
+	}
+}
+
+/// A block of metadata at the top of a document, or a whole metadata file
+#[derive(Clone, Debug)]
+pub struct Frontmatter<'a> {
+	/// What to call this block in error messages
+	pub name: &'static str,
+	pub format: Format,
+	pub source: &'a str,
+	/// How many lines of the file come before `source`, so that we can report
+	/// locations relative to the whole file
+	pub line_offset: usize,
+}
+
+/// Splits a document into its frontmatter, if it has any, and the rest of its lines.
+/// Leading blank lines are skipped either way.
+pub fn split(content: &str) -> Result<(Option<Frontmatter<'_>>, Vec<&str>), BuildError> {
+	let lines = content
+		.lines()
+		.enumerate()
+		.skip_while(|(_, line)| line.trim().is_empty())
+		.collect::<Vec<_>>();
+
+	let Some(&(first_index, first_line)) = lines.first() else {
+		return Ok((None, Vec::new()));
+	};
+
+	let is_delimiter = |line: &str, c: char| line.len() >= 3 && line.find(|it| it != c).is_none();
+	let delimiter = match first_line {
+		line if is_delimiter(line, '-') => Some('-'),
+		line if is_delimiter(line, '+') => Some('+'),
+		_ => None,
+	};
+
+	if let Some(delimiter) = delimiter {
+		let end = lines
+			.iter()
+			.skip(1)
+			.position(|(_, line)| is_delimiter(line, delimiter))
+			.map(|position| position + 1);
+		let block = &lines[1..end.unwrap_or(lines.len())];
+
+		// Talks use `+++` to separate slides, so a `+++` block is only frontmatter if it
+		// is closed, and actually starts by setting a key
+		let is_frontmatter = match delimiter {
+			'+' => end.is_some() && looks_like_toml(block),
+			_ => true,
+		};
+
+		if is_frontmatter {
+			let source = match (block.first(), block.last()) {
+				(Some(&(_, first)), Some(&(_, last))) => slice_between(content, first, last),
+				_ => "",
+			};
+			let rest = lines
+				.get(end.unwrap_or(lines.len()) + 1..)
+				.unwrap_or_default();
+
+			return Ok((
+				Some(Frontmatter {
+					name: "frontmatter",
+					format: if delimiter == '+' {
+						Format::Toml
+					} else {
+						Format::Yaml
+					},
+					source,
+					line_offset: first_index + 1,
+				}),
+				rest.iter().map(|(_, line)| *line).collect(),
+			));
+		}
+	}
+
+	if first_line.starts_with('{') {
+		// Let `serde_json` tell us where the object ends
+		let start = offset_of(content, first_line);
+		let mut stream =
+			serde_json::Deserializer::from_str(&content[start..]).into_iter::<IgnoredAny>();
+		if let Some(Err(err)) = stream.next() {
+			return Err(
+				BuildError::new(format!(
+					"invalid frontmatter: {}",
+					without_location(&err.to_string())
+				))
+				.at(first_index + err.line(), err.column())
+				.with_hint("frontmatter is written in JSON, so check for unbalanced quotes or brackets"),
+			);
+		}
+
+		let source = &content[start..start + stream.byte_offset()];
+		let last_index = first_index + source.lines().count().max(1) - 1;
+		let rest = lines
+			.iter()
+			.skip_while(|(index, _)| *index <= last_index)
+			.map(|(_, line)| *line)
+			.collect();
+
+		return Ok((
+			Some(Frontmatter {
+				name: "frontmatter",
+				format: Format::Json,
+				source,
+				line_offset: first_index,
+			}),
+			rest,
+		));
+	}
+
+	Ok((None, lines.into_iter().map(|(_, line)| line).collect()))
+}
+
+fn looks_like_toml(block: &[(usize, &str)]) -> bool {
+	let Some((_, line)) = block.iter().find(|(_, line)| !line.trim().is_empty()) else {
+		return false;
+	};
+
+	let line = line.trim();
+	let is_key_character = |c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '.';
+	(line.starts_with('[') && line.ends_with(']'))
+		|| line
+			.split_once('=')
+			.is_some_and(|(key, _)| !key.trim().is_empty() && key.trim().chars().all(is_key_character))
+}
+
+/// Where a subslice of `content` starts, in bytes
+fn offset_of(content: &str, slice: &str) -> usize {
+	slice.as_ptr() as usize - content.as_ptr() as usize
+}
+
+fn slice_between<'a>(content: &'a str, first: &str, last: &str) -> &'a str {
+	&content[offset_of(content, first)..offset_of(content, last) + last.len()]
+}
+
+/// `serde_yaml` and `serde_json` include the location in their messages, but it's
+/// relative to the frontmatter rather than the file, so we report our own instead.
+pub fn without_location(message: &str) -> &str {
+	match message.find(" at line ") {
+		Some(index) => &message[..index],
+		None => message,
+	}
+}
+
+impl<'a> Frontmatter<'a> {
+	/// Deserializes the metadata, and returns it along with a warning for each key that
+	/// `M` doesn't know about.
+	pub fn deserialize<M>(&self) -> Result<(M, Vec<BuildError>), BuildError>
+	where
+		M: DeserializeOwned,
+	{
+		let metadata = match self.format {
+			Format::Yaml => {
+				let deserializer = serde_yaml::Deserializer::from_str(self.source);
+				serde_path_to_error::deserialize(deserializer).map_err(|err| {
+					let location = err
+						.inner()
+						.location()
+						.map(|location| (location.line(), location.column()));
+					let message = without_location(&err.inner().to_string()).to_string();
+					self.error(err.path(), message, location)
+				})?
+			}
+			Format::Toml => {
+				let deserializer = toml::Deserializer::new(self.source);
+				serde_path_to_error::deserialize(deserializer).map_err(|err| {
+					let location = err
+						.inner()
+						.span()
+						.map(|span| line_and_column(self.source, span.start));
+					let message = err.inner().message().trim().to_string();
+					self.error(err.path(), message, location)
+				})?
+			}
+			Format::Json => {
+				let mut deserializer = serde_json::Deserializer::from_str(self.source);
+				serde_path_to_error::deserialize(&mut deserializer).map_err(|err| {
+					let location = Some((err.inner().line(), err.inner().column()));
+					let message = without_location(&err.inner().to_string()).to_string();
+					self.error(err.path(), message, location)
+				})?
+			}
+		};
+
+		Ok((metadata, self.unknown_field_warnings::<M>()))
+	}
+
+	/// Parses the source without any particular structure in mind
+	pub fn value<T>(&self) -> Option<T>
+	where
+		T: DeserializeOwned,
+	{
+		match self.format {
+			Format::Yaml => serde_yaml::from_str(self.source).ok(),
+			Format::Toml => toml::from_str(self.source).ok(),
+			Format::Json => serde_json::from_str(self.source).ok(),
+		}
+	}
+
+	fn keys(&self) -> Vec<String> {
+		self
+			.value::<serde_json::Map<String, serde_json::Value>>()
+			.map(|object| object.keys().cloned().collect())
+			.unwrap_or_default()
+	}
+
+	/// Looks for any keys that would be silently ignored during deserialization
+	fn unknown_field_warnings<M>(&self) -> Vec<BuildError>
+	where
+		M: DeserializeOwned,
+	{
+		let keys = self.keys();
+		unknown_fields::<M, _>(keys.iter().map(String::as_str))
+			.into_iter()
+			.map(|unknown| {
+				let (line, column) = self
+					.key_location(&unknown.key)
+					.map(|(line, (key_column, _))| (line, key_column))
+					.unwrap_or((1, 1));
+				let warning =
+					BuildError::new(unknown.message(self.name)).at(self.line_offset + line, column);
+				match unknown.hint() {
+					Some(hint) => warning.with_hint(hint),
+					None => warning,
+				}
+			})
+			.collect()
+	}
+
+	/// Finds the line where a top level key is set, along with its columns
+	fn key_location(&self, key: &str) -> Option<(usize, (usize, usize))> {
+		self
+			.source
+			.lines()
+			.enumerate()
+			.find_map(|(index, line)| Some((index + 1, self.format.key_columns(line, key)?)))
+	}
+
+	/// Builds an error that points at the offending line of the file. The parsers only
+	/// know the location of syntax and type errors, so for everything else we look for
+	/// the line where the failing field is set.
+	fn error(
+		&self,
+		path: &serde_path_to_error::Path,
+		mut message: String,
+		location: Option<(usize, usize)>,
+	) -> BuildError {
+		let field = path.iter().find_map(|segment| match segment {
+			Segment::Map { key } => Some(key.as_str()),
+			_ => None,
+		});
+
+		if let Some(field) = field.filter(|field| !message.starts_with(*field)) {
+			message = format!("{}: {}", field, message);
+		}
+
+		let mut error = BuildError::new(format!("invalid {}: {}", self.name, message));
+		if message.starts_with("missing field") {
+			error = error.with_hint(format!(
+				"required fields need to be set in the {}",
+				self.name
+			));
+		} else if field.is_none() {
+			error = error.with_hint(format!(
+				"{} is written in {}, so check for unbalanced quotes or brackets",
+				self.name,
+				self.format.name()
+			));
+		}
+
+		let field_location = field
+			.and_then(|field| self.key_location(field))
+			.map(|(line, (_, value_column))| (line, value_column));
+
+		match field_location.or(location) {
+			Some((line, column)) => error.at(self.line_offset + line, column),
+			None => error.at(self.line_offset + 1, 1),
+		}
+	}
+}
+
+fn line_and_column(source: &str, offset: usize) -> (usize, usize) {
+	let before = &source[..offset.min(source.len())];
+	let line = before.matches('\n').count() + 1;
+	let column = before.len() - before.rfind('\n').map(|index| index + 1).unwrap_or(0) + 1;
+	(line, column)
+}
@@ -0,0 +1,84 @@
+use url::Url;
+
+/// Attributes that point at another resource, and so need to be rewritten when the
+/// html is read from somewhere other than the page itself
+const URL_ATTRIBUTES: &[&str] = &["href", "src", "poster"];
+
+/// Resolves `url` against `base`, or leaves it alone if it can't be resolved. Feed
+/// readers show content out of context of the page, so a link like
+/// `/resources/cat.png` would otherwise point at nothing.
+pub fn absolute_url(url: &str, base: &Url) -> String {
+	match base.join(url) {
+		Ok(url) => url.to_string(),
+		Err(_) => url.to_string(),
+	}
+}
+
+/// Rewrites every link and image path in a snippet of raw html to be absolute. This
+/// only understands html that was written by hand, so it shouldn't be given anything
+/// that might contain code samples, where attribute-like text isn't really markup.
+pub fn absolutize(html: &str, base: &Url) -> String {
+	let mut result = String::with_capacity(html.len());
+	let mut rest = html;
+
+	while let Some((start, end)) = next_url(rest) {
+		result.push_str(&rest[..start]);
+		let value = &rest[start..end];
+		match base.join(&unescape(value)) {
+			Ok(url) => result.push_str(&url.as_str().replace('&', "&amp;")),
+			Err(_) => result.push_str(value),
+		}
+		rest = &rest[end..];
+	}
+
+	result.push_str(rest);
+	result
+}
+
+/// Finds the byte range of the next url attribute value in `html`
+fn next_url(html: &str) -> Option<(usize, usize)> {
+	let mut offset = 0;
+
+	while let Some(index) = html[offset..].find('=') {
+		let equals = offset + index;
+		offset = equals + 1;
+
+		let name = html[..equals].trim_end();
+		let is_url_attribute = URL_ATTRIBUTES.iter().any(|attribute| {
+			name.len() > attribute.len()
+				&& name.ends_with(attribute)
+				&& name[..name.len() - attribute.len()].ends_with(char::is_whitespace)
+		});
+		if !is_url_attribute {
+			continue;
+		}
+
+		let value = html[offset..].trim_start();
+		let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+		let start = html.len() - value.len() + 1;
+		let end = start + html[start..].find(quote)?;
+		return Some((start, end));
+	}
+
+	None
+}
+
+fn unescape(value: &str) -> String {
+	value.replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn absolute_links() {
+		let base = Url::parse("https://mckayla.blog/posts/cool-video-games.html").unwrap();
+		let html = r##"<p><a href="/resources/cat.png">cat</a> <img src='dog.png' /> <a href="https://example.com/?a=1&amp;b=2">ok</a> <a href="#notes">notes</a></p>"##;
+
+		assert_eq!(
+			absolutize(html, &base),
+			r##"<p><a href="https://mckayla.blog/resources/cat.png">cat</a> <img src='https://mckayla.blog/posts/dog.png' /> <a href="https://example.com/?a=1&amp;b=2">ok</a> <a href="https://mckayla.blog/posts/cool-video-games.html#notes">notes</a></p>"##
+		);
+	}
+}
@@ -1,24 +1,611 @@
+use once_cell::sync::Lazy;
+use pulldown_cmark::CodeBlockKind;
+use pulldown_cmark::Event;
+use pulldown_cmark::Tag;
+use serde::Serialize;
+use std::collections::HashSet;
+use syntect::highlighting::Color;
+use syntect::highlighting::Theme;
+use syntect::highlighting::ThemeSet;
+use syntect::html::css_for_theme_with_class_style;
+use syntect::html::ClassStyle;
+use syntect::html::ClassedHTMLGenerator;
+use syntect::parsing::SyntaxDefinition;
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+use url::Url;
+
+use crate::pocky::escape::escape;
+use crate::pocky::links::absolute_url;
+use crate::pocky::links::absolutize;
+use crate::pocky::slug::slugify;
+
+/// Highlighted code only gets classes, and the colors come from `highlight_css`, so
+/// that the theme can follow the light/dark preference of the reader
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+/// The grammars that come with syntect, along with the ones that we bundle for
+/// languages that it doesn't know about
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(|| {
+	let mut syntaxes = SyntaxSet::load_defaults_newlines().into_builder();
+	for source in [
+		include_str!("./syntaxes/gleam.sublime-syntax"),
+		include_str!("./syntaxes/zig.sublime-syntax"),
+	] {
+		syntaxes
+			.add(SyntaxDefinition::load_from_str(source, true, None).expect("invalid bundled syntax"));
+	}
+	syntaxes.build()
+});
+
 pub fn markdown_to_html<S>(md_source: S) -> String
 where
 	S: AsRef<str>,
 {
-	let options = {
-		use pulldown_cmark::Options;
+	render(md_source, false, None).html
+}
 
-		let mut options = Options::empty();
-		options.insert(Options::ENABLE_FOOTNOTES);
-		options.insert(Options::ENABLE_STRIKETHROUGH);
-		options.insert(Options::ENABLE_TABLES);
-		options.insert(Options::ENABLE_TASKLISTS);
+/// A rendered markdown document, along with the structure of its headings
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Document {
+	pub html: String,
+	pub toc: Vec<TocEntry>,
+}
 
-		options
-	};
+/// A heading in the table of contents, with any headings nested under it
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct TocEntry {
+	pub level: usize,
+	pub id: String,
+	pub title: String,
+	pub children: Vec<TocEntry>,
+}
+
+/// The markdown extensions that we render with
+fn options() -> pulldown_cmark::Options {
+	use pulldown_cmark::Options;
+
+	let mut options = Options::empty();
+	options.insert(Options::ENABLE_FOOTNOTES);
+	options.insert(Options::ENABLE_STRIKETHROUGH);
+	options.insert(Options::ENABLE_TABLES);
+	options.insert(Options::ENABLE_TASKLISTS);
+
+	options
+}
 
+/// Renders markdown to html. Every heading gets an `id`, so that it can be linked to,
+/// and if `anchors` is set, a link to itself that shows up when hovered. If `base` is
+/// given, every link and image is made absolute by resolving it against `base`.
+pub fn render<S>(md_source: S, anchors: bool, base: Option<&Url>) -> Document
+where
+	S: AsRef<str>,
+{
 	// Allocate *roughly* enough room. It'll probably resize at least once, but this
 	// should prevent it from needing to resize multiple times.
 	let md_source = md_source.as_ref();
-	let mut result = String::with_capacity(md_source.len());
-	let parser = pulldown_cmark::Parser::new_ext(md_source, options);
-	pulldown_cmark::html::push_html(&mut result, parser);
-	result
+	let mut html = String::with_capacity(md_source.len());
+	let parser = pulldown_cmark::Parser::new_ext(md_source, options());
+	let events = parser.map(|event| match base {
+		Some(base) => absolute_links(event, base),
+		None => event,
+	});
+	let (events, toc) = heading_ids(highlight_code_blocks(events), anchors, base);
+	pulldown_cmark::html::push_html(&mut html, events.into_iter());
+
+	Document { html, toc }
+}
+
+/// Makes the url of a link or image absolute. Raw html gets the same treatment, but
+/// code is left alone, since it only looks like markup.
+fn absolute_links<'a>(event: Event<'a>, base: &Url) -> Event<'a> {
+	match event {
+		Event::Start(Tag::Link(kind, url, title)) => {
+			Event::Start(Tag::Link(kind, absolute_url(&url, base).into(), title))
+		}
+		Event::Start(Tag::Image(kind, url, title)) => {
+			Event::Start(Tag::Image(kind, absolute_url(&url, base).into(), title))
+		}
+		Event::Html(html) => Event::Html(absolutize(&html, base).into()),
+		event => event,
+	}
+}
+
+/// Gives every heading a unique `id` based on its text, and collects them into a
+/// table of contents
+fn heading_ids<'a, I>(
+	events: I,
+	anchors: bool,
+	base: Option<&Url>,
+) -> (Vec<Event<'a>>, Vec<TocEntry>)
+where
+	I: Iterator<Item = Event<'a>>,
+{
+	let mut result = Vec::new();
+	let mut toc = Vec::new();
+	let mut ids = HashSet::new();
+	let mut heading: Option<(usize, String, Vec<Event<'a>>)> = None;
+
+	for event in events {
+		match (&mut heading, event) {
+			(None, Event::Start(Tag::Heading(level, _, _))) => {
+				heading = Some((level as usize, String::new(), Vec::new()));
+			}
+			(Some(_), Event::End(Tag::Heading(..))) => {
+				let (level, title, inner) = heading.take().unwrap();
+
+				let slug = match slugify(&title) {
+					slug if slug.is_empty() => "section".to_string(),
+					slug => slug,
+				};
+				let mut id = slug.clone();
+				let mut count = 1;
+				while !ids.insert(id.clone()) {
+					id = format!("{}-{}", slug, count);
+					count += 1;
+				}
+
+				result.push(Event::Html(format!("<h{} id=\"{}\">", level, id).into()));
+				result.extend(inner);
+				if anchors {
+					let href = format!("#{}", id);
+					let href = match base {
+						Some(base) => absolute_url(&href, base),
+						None => href,
+					};
+					result.push(Event::Html(
+						format!(
+							"<a class=\"anchor\" href=\"{}\" aria-label=\"Link to this section\">#</a>",
+							href
+						)
+						.into(),
+					));
+				}
+				result.push(Event::Html(format!("</h{}>\n", level).into()));
+
+				insert_toc_entry(
+					&mut toc,
+					TocEntry {
+						level,
+						id,
+						title,
+						children: Vec::new(),
+					},
+				);
+			}
+			(Some((_, title, inner)), event) => {
+				if let Event::Text(text) | Event::Code(text) = &event {
+					title.push_str(text);
+				}
+				inner.push(event);
+			}
+			(None, event) => result.push(event),
+		}
+	}
+
+	(result, toc)
+}
+
+/// Nests an entry under the last heading with a lower level, if there is one
+fn insert_toc_entry(entries: &mut Vec<TocEntry>, entry: TocEntry) {
+	match entries.last_mut() {
+		Some(last) if last.level < entry.level => insert_toc_entry(&mut last.children, entry),
+		_ => entries.push(entry),
+	}
+}
+
+/// Counts the words in a markdown document that a reader would actually read, which
+/// leaves out code blocks, and any html (like comments)
+pub fn word_count<S>(md_source: S) -> usize
+where
+	S: AsRef<str>,
+{
+	let mut in_code_block = false;
+	pulldown_cmark::Parser::new_ext(md_source.as_ref(), options())
+		.filter_map(|event| match event {
+			Event::Start(Tag::CodeBlock(_)) => {
+				in_code_block = true;
+				None
+			}
+			Event::End(Tag::CodeBlock(_)) => {
+				in_code_block = false;
+				None
+			}
+			Event::Text(text) | Event::Code(text) if !in_code_block => {
+				Some(text.split_whitespace().count())
+			}
+			_ => None,
+		})
+		.sum()
+}
+
+/// Everything above this marker becomes the excerpt of a post
+const MORE_MARKER: &str = "more";
+
+/// Roughly how long an excerpt can be, in characters, before we cut it short
+const EXCERPT_LENGTH: usize = 300;
+
+/// A short version of a document, for places like the index and feeds
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Excerpt {
+	pub html: String,
+	pub text: String,
+}
+
+impl Excerpt {
+	/// An excerpt from something that was already rendered, like a `summary`
+	pub fn from_html(html: &str) -> Self {
+		Excerpt {
+			html: html.to_string(),
+			text: html_to_text(html),
+		}
+	}
+}
+
+fn is_more_marker(html: &str) -> bool {
+	html
+		.trim()
+		.strip_prefix("<!--")
+		.and_then(|comment| comment.strip_suffix("-->"))
+		.is_some_and(|comment| comment.trim() == MORE_MARKER)
+}
+
+/// Uses everything above a `<!-- more -->` marker if there is one, or otherwise the
+/// first paragraph, cut short at the end of a sentence if it's too long
+pub fn excerpt<S>(md_source: S) -> Option<Excerpt>
+where
+	S: AsRef<str>,
+{
+	let events = pulldown_cmark::Parser::new(md_source.as_ref()).collect::<Vec<_>>();
+
+	if let Some(marker) = events
+		.iter()
+		.position(|event| matches!(event, Event::Html(html) if is_more_marker(html)))
+	{
+		let events = &events[..marker];
+		let mut html = String::new();
+		pulldown_cmark::html::push_html(&mut html, events.iter().cloned());
+		return Some(Excerpt {
+			html,
+			text: plain_text(events),
+		});
+	}
+
+	let start = events
+		.iter()
+		.position(|event| matches!(event, Event::Start(Tag::Paragraph)))?;
+	let end = start
+		+ events[start..]
+			.iter()
+			.position(|event| matches!(event, Event::End(Tag::Paragraph)))?;
+	let paragraph = &events[start..=end];
+	let text = plain_text(paragraph);
+
+	if text.len() <= EXCERPT_LENGTH {
+		let mut html = String::new();
+		pulldown_cmark::html::push_html(&mut html, paragraph.iter().cloned());
+		return Some(Excerpt { html, text });
+	}
+
+	let text = truncate(&text);
+	let mut html = String::from("<p>");
+	pulldown_cmark::escape::escape_html(&mut html, &text).expect("failed to escape html");
+	html.push_str("</p>\n");
+	Some(Excerpt { html, text })
+}
+
+/// The text of some markdown, without any formatting or html
+fn plain_text(events: &[Event]) -> String {
+	let mut text = String::new();
+	let mut in_code_block = false;
+	for event in events {
+		match event {
+			Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+			Event::End(Tag::CodeBlock(_)) => in_code_block = false,
+			Event::Text(content) | Event::Code(content) if !in_code_block => text.push_str(content),
+			Event::SoftBreak | Event::HardBreak | Event::End(_) => text.push(' '),
+			_ => {}
+		}
+	}
+
+	text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Cuts text down to `EXCERPT_LENGTH`, preferably at the end of a sentence
+fn truncate(text: &str) -> String {
+	let mut end = EXCERPT_LENGTH;
+	while !text.is_char_boundary(end) {
+		end -= 1;
+	}
+	let text = &text[..end];
+
+	let sentence_end = text
+		.match_indices(['.', '!', '?'])
+		.map(|(index, _)| index + 1)
+		.rfind(|index| text[*index..].starts_with(' '));
+
+	match sentence_end {
+		Some(index) => text[..index].to_string(),
+		None => format!(
+			"{}\u{2026}",
+			text[..text.rfind(' ').unwrap_or(text.len())].trim_end()
+		),
+	}
+}
+
+/// Strips the tags out of html that we rendered ourselves, leaving just the text
+pub fn html_to_text(html: &str) -> String {
+	let mut text = String::with_capacity(html.len());
+	let mut in_tag = false;
+	for c in html.chars() {
+		match c {
+			'<' => in_tag = true,
+			'>' if in_tag => {
+				in_tag = false;
+				text.push(' ');
+			}
+			_ if !in_tag => text.push(c),
+			_ => {}
+		}
+	}
+
+	text
+		.replace("&lt;", "<")
+		.replace("&gt;", ">")
+		.replace("&quot;", "\"")
+		.replace("&#39;", "'")
+		.replace("&amp;", "&")
+		.split_whitespace()
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// Pulls the html comments out of some markdown, returning the markdown without them,
+/// along with the text of each one. Comments inside of code are left alone, and a
+/// comment on a line of its own takes the whole line with it, so that lists stay tight.
+pub fn split_comments(md_source: &str) -> (String, Vec<String>) {
+	let code = pulldown_cmark::Parser::new(md_source)
+		.into_offset_iter()
+		.filter_map(|(event, range)| match event {
+			Event::Start(Tag::CodeBlock(_)) | Event::Code(_) => Some(range),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+
+	let mut rest = String::with_capacity(md_source.len());
+	let mut comments = Vec::new();
+	let mut position = 0;
+	while let Some(start) = md_source[position..]
+		.find("<!--")
+		.map(|index| position + index)
+	{
+		if let Some(range) = code.iter().find(|range| range.contains(&start)) {
+			rest.push_str(&md_source[position..range.end]);
+			position = range.end;
+			continue;
+		}
+		let Some(end) = md_source[start..]
+			.find("-->")
+			.map(|index| start + index + 3)
+		else {
+			break;
+		};
+		comments.push(dedent(&md_source[start + 4..end - 3]));
+
+		let line_start = md_source[..start].rfind('\n').map_or(0, |index| index + 1);
+		let line_end = md_source[end..]
+			.find('\n')
+			.map_or(md_source.len(), |index| end + index + 1);
+		if line_start >= position
+			&& md_source[line_start..start].trim().is_empty()
+			&& md_source[end..line_end].trim().is_empty()
+		{
+			rest.push_str(&md_source[position..line_start]);
+			position = line_end;
+		} else {
+			rest.push_str(&md_source[position..start]);
+			position = end;
+		}
+	}
+	rest.push_str(&md_source[position..]);
+
+	(rest, comments)
+}
+
+/// Removes the indentation that comes from where a comment sits in the document
+fn dedent(text: &str) -> String {
+	// The first line starts right after the `<!--`, so it doesn't count
+	let indent = text
+		.lines()
+		.skip(1)
+		.filter(|line| !line.trim().is_empty())
+		.map(|line| line.len() - line.trim_start().len())
+		.min()
+		.unwrap_or(0);
+
+	text
+		.lines()
+		.enumerate()
+		.map(|(index, line)| match index {
+			0 => line.trim_start(),
+			_ => line.get(indent..).unwrap_or(line.trim_start()),
+		})
+		.collect::<Vec<_>>()
+		.join("\n")
+		.trim()
+		.to_string()
+}
+
+/// Replaces fenced code blocks in a known language with highlighted html. Anything
+/// that we can't highlight is passed through untouched.
+fn highlight_code_blocks<'a, I>(events: I) -> impl Iterator<Item = Event<'a>>
+where
+	I: Iterator<Item = Event<'a>>,
+{
+	let mut code_block: Option<(String, String, Vec<Event<'a>>)> = None;
+
+	events.flat_map(move |event| match (&mut code_block, event) {
+		(None, Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)))) => {
+			let language = info
+				.split(|c: char| c.is_whitespace() || c == ',')
+				.next()
+				.unwrap_or_default()
+				.to_string();
+			let start = Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info)));
+			code_block = Some((language, String::new(), vec![start]));
+			Vec::new()
+		}
+		(Some((_, code, events)), Event::Text(text)) => {
+			code.push_str(&text);
+			events.push(Event::Text(text));
+			Vec::new()
+		}
+		(Some(_), event @ Event::End(Tag::CodeBlock(_))) => {
+			let (language, code, mut events) = code_block.take().unwrap();
+			match highlight(&code, &language) {
+				Some(html) => vec![Event::Html(html.into())],
+				None => {
+					events.push(event);
+					events
+				}
+			}
+		}
+		(Some((_, _, events)), event) => {
+			events.push(event);
+			Vec::new()
+		}
+		(None, event) => vec![event],
+	})
+}
+
+/// Highlights `code` as `language`, or returns `None` if we don't know the language
+pub fn highlight(code: &str, language: &str) -> Option<String> {
+	if language.is_empty() {
+		return None;
+	}
+
+	let syntax = SYNTAXES.find_syntax_by_token(language)?;
+	let mut generator = ClassedHTMLGenerator::new_with_class_style(syntax, &SYNTAXES, CLASS_STYLE);
+	for line in LinesWithEndings::from(code) {
+		generator
+			.parse_html_for_line_which_includes_newline(line)
+			.ok()?;
+	}
+
+	Some(format!(
+		"<pre class=\"highlight\"><code class=\"language-{}\">{}</code></pre>\n",
+		escape(language),
+		generator.finalize()
+	))
+}
+
+/// The stylesheet for highlighted code, using the light or dark theme depending on
+/// the preference of the reader
+pub fn highlight_css() -> String {
+	let themes = ThemeSet::load_defaults();
+	let css = |theme: &Theme| {
+		let background = theme.settings.background.unwrap_or(Color::WHITE);
+		let rules = css_for_theme_with_class_style(theme, CLASS_STYLE).expect("invalid bundled theme");
+		format!(
+			"pre.highlight {{\n\tbackground-color: #{:02x}{:02x}{:02x};\n}}\n\n{}",
+			background.r, background.g, background.b, rules
+		)
+	};
+
+	format!(
+		"pre.highlight {{\n\tpadding: 1em;\n\toverflow: auto;\n\tborder-radius: 0.3em;\n}}\n\n{}\n@media (prefers-color-scheme: dark) {{\n{}}}\n",
+		css(&themes.themes[LIGHT_THEME]),
+		css(&themes.themes[DARK_THEME]),
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn highlights_fenced_code() {
+		let html = markdown_to_html("```gleam\npub fn main() {}\n```\n");
+		assert!(html.starts_with("<pre class=\"highlight\"><code class=\"language-gleam\">"));
+		assert!(html.contains("<span class=\"hl-storage hl-modifier hl-gleam\">pub</span>"));
+
+		let html = markdown_to_html("```cobol\nDISPLAY 'HI'.\n```\n");
+		assert_eq!(
+			html,
+			"<pre><code class=\"language-cobol\">DISPLAY 'HI'.\n</code></pre>\n"
+		);
+	}
+
+	#[test]
+	fn absolute_links() {
+		let base = Url::parse("https://mckayla.blog/posts/cool-video-games.html").unwrap();
+		let html = render(
+			"[cat](/cat.png) ![dog](dog.png) `<a href='/x'>`\n\n<img src=\"bird.png\" />\n\n```\n<a href='/y'>\n```\n",
+			false,
+			Some(&base),
+		)
+		.html;
+
+		assert!(html.contains("<a href=\"https://mckayla.blog/cat.png\">"));
+		assert!(html.contains("<img src=\"https://mckayla.blog/posts/dog.png\""));
+		assert!(html.contains("<img src=\"https://mckayla.blog/posts/bird.png\" />"));
+		assert!(html.contains("<code>&lt;a href='/x'&gt;</code>"));
+		assert!(html.contains("&lt;a href='/y'&gt;"));
+	}
+
+	#[test]
+	fn counts_words() {
+		let source =
+			"# Cool video games\n<!-- not yet -->\nThey're `really` fun!\n\n```rust\nfn main() {}\n```\n";
+		assert_eq!(word_count(source), 6);
+
+		let table = "| Game | Hours |\n| --- | ---: |\n| Celeste | 40 |\n";
+		assert_eq!(word_count(table), 4);
+	}
+
+	#[test]
+	fn excerpts() {
+		let excerpt =
+			excerpt("# Hi\nThis is *the* intro.\n\nStill the intro.\n<!-- more -->\nThe rest.\n")
+				.unwrap();
+		assert_eq!(excerpt.text, "Hi This is the intro. Still the intro.");
+		assert!(excerpt.html.contains("<em>the</em>"));
+		assert!(!excerpt.html.contains("The rest"));
+
+		let long = format!(
+			"## Prelude\n{} And then some.\n\nNope.\n",
+			"Cool video games. ".repeat(20)
+		);
+		let excerpt = super::excerpt(long).unwrap();
+		assert!(excerpt.text.ends_with("games."));
+		assert!(excerpt.text.len() <= EXCERPT_LENGTH);
+		assert_eq!(excerpt.html, format!("<p>{}</p>\n", excerpt.text));
+	}
+
+	#[test]
+	fn heading_ids_and_toc() {
+		let document = render("# Setup\n## `ssh` keys\n## Setup\n# Done\n", true, None);
+
+		assert!(document
+			.html
+			.starts_with("<h1 id=\"setup\">Setup<a class=\"anchor\" href=\"#setup\""));
+		assert!(document.html.contains("<h2 id=\"setup-1\">"));
+		assert_eq!(document.toc.len(), 2);
+		assert_eq!(document.toc[0].children[0].id, "ssh-keys");
+		assert_eq!(document.toc[0].children[1].id, "setup-1");
+		assert_eq!(document.toc[1].title, "Done");
+	}
+
+	#[test]
+	fn splits_comments() {
+		let (rest, comments) = split_comments(
+			"- lock files\n  <!-- massive -->\n- versions\n  <!--\n  - old\n  - new\n  -->\n- `<!-- code -->` <!-- wait -->\n",
+		);
+
+		assert_eq!(rest, "- lock files\n- versions\n- `<!-- code -->` \n");
+		assert_eq!(comments, vec!["massive", "- old\n- new", "wait"]);
+	}
 }
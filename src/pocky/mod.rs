@@ -1,10 +1,20 @@
 pub mod de;
+mod error;
+pub mod escape;
+pub mod fields;
+pub mod frontmatter;
+pub mod links;
 pub mod md;
 mod page;
 pub mod ser;
+pub mod slug;
 
+pub use error::BuildError;
+pub use error::Diagnostics;
+#[allow(unused_imports)]
 pub use page::html::HtmlPage;
 pub use page::md::MarkdownPage;
 pub use page::pages_from_directory;
 pub use page::text::TextPage;
 pub use page::AsHtml;
+pub use page::FromFile;
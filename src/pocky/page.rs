@@ -4,16 +4,21 @@ pub mod text;
 
 use std::fs;
 use std::path::Path;
-use std::path::PathBuf;
+
+use crate::pocky::BuildError;
 
 pub trait AsHtml {
 	fn as_html(&self) -> String;
 }
 
-pub fn pages_from_directory<E, P>(path: P) -> impl Iterator<Item = E>
+pub trait FromFile: Sized {
+	fn from_file(path: &Path) -> Result<Self, BuildError>;
+}
+
+pub fn pages_from_directory<E, P>(path: P) -> impl Iterator<Item = Result<E, BuildError>>
 where
 	P: AsRef<Path>,
-	E: AsHtml + From<PathBuf>,
+	E: AsHtml + FromFile,
 {
 	fs::read_dir(path)
 		.expect("could not read directory contents")
@@ -24,5 +29,5 @@ where
 				.map(|file_type| file_type.is_file())
 				.unwrap_or(false)
 		})
-		.map(|entry| E::from(entry.path()))
+		.map(|entry| E::from_file(&entry.path()))
 }
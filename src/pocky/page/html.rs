@@ -1,7 +1,9 @@
 use std::fs;
 use std::path::Path;
 
-use crate::AsHtml;
+use crate::pocky::AsHtml;
+use crate::pocky::BuildError;
+use crate::pocky::FromFile;
 
 #[derive(Clone, Debug)]
 pub struct HtmlPage {
@@ -15,13 +17,11 @@ impl HtmlPage {
 	}
 }
 
-impl<P> From<P> for HtmlPage
-where
-	P: AsRef<Path>,
-{
-	fn from(path: P) -> Self {
-		let content = fs::read_to_string(path).expect("unable to read file");
-		HtmlPage { content }
+impl FromFile for HtmlPage {
+	fn from_file(path: &Path) -> Result<Self, BuildError> {
+		let content = fs::read_to_string(path)
+			.map_err(|err| BuildError::new(format!("unable to read file: {}", err)).in_file(path))?;
+		Ok(HtmlPage { content })
 	}
 }
 
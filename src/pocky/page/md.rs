@@ -2,33 +2,64 @@ use serde::de::DeserializeOwned;
 use std::fs;
 use std::path::Path;
 
-use crate::pocky::md::markdown_to_html;
+use crate::pocky::md;
+use crate::pocky::md::Excerpt;
+use crate::pocky::md::TocEntry;
 use crate::pocky::page::text::TextPage;
 use crate::pocky::AsHtml;
+use crate::pocky::BuildError;
+use crate::pocky::FromFile;
 
 #[derive(Clone, Debug)]
 pub struct MarkdownPage<M: DeserializeOwned> {
 	pub metadata: Option<M>,
+	/// The markdown that `content` was rendered from
+	pub source: String,
 	pub content: String,
+	/// Every heading in the page, nested by level
+	pub toc: Vec<TocEntry>,
+	pub word_count: usize,
+	pub excerpt: Option<Excerpt>,
+	pub warnings: Vec<BuildError>,
 }
 
 impl<M: DeserializeOwned> MarkdownPage<M> {
-	pub fn parse(content: String) -> Self {
-		let TextPage { metadata, content } = TextPage::parse(content);
-		let content = markdown_to_html(content);
+	pub fn parse(content: String) -> Result<Self, BuildError> {
+		let TextPage {
+			metadata,
+			content,
+			warnings,
+		} = TextPage::parse(content)?;
+		let word_count = md::word_count(&content);
+		let excerpt = md::excerpt(&content);
+		let document = md::render(&content, true, None);
 
-		MarkdownPage { metadata, content }
+		Ok(MarkdownPage {
+			metadata,
+			source: content,
+			content: document.html,
+			toc: document.toc,
+			word_count,
+			excerpt,
+			warnings,
+		})
 	}
 }
 
-impl<M, P> From<P> for MarkdownPage<M>
+impl<M> FromFile for MarkdownPage<M>
 where
-	P: AsRef<Path>,
 	M: DeserializeOwned,
 {
-	fn from(path: P) -> Self {
-		let content = fs::read_to_string(path).expect("unable to read file");
-		MarkdownPage::parse(content)
+	fn from_file(path: &Path) -> Result<Self, BuildError> {
+		let content = fs::read_to_string(path)
+			.map_err(|err| BuildError::new(format!("unable to read file: {}", err)).in_file(path))?;
+		let mut page = MarkdownPage::parse(content).map_err(|err| err.in_file(path))?;
+		page.warnings = page
+			.warnings
+			.into_iter()
+			.map(|warning| warning.in_file(path))
+			.collect();
+		Ok(page)
 	}
 }
 
@@ -49,12 +80,14 @@ mod tests {
 	use serde::Deserialize;
 	use std::collections::HashMap;
 
+	const HELLO_FRIEND: &str = "<h1 id=\"hello-friend\">Hello, friend!<a class=\"anchor\" href=\"#hello-friend\" aria-label=\"Link to this section\">#</a></h1>\n";
+
 	#[test]
 	fn no_frontmatter() {
-		let page = BasicMarkdownPage::parse("# Hello, friend!\n".to_string());
+		let page = BasicMarkdownPage::parse("# Hello, friend!\n".to_string()).unwrap();
 
 		assert_eq!(page.metadata, None);
-		assert_eq!(page.content, "<h1>Hello, friend!</h1>\n");
+		assert_eq!(page.content, HELLO_FRIEND);
 	}
 
 	#[test]
@@ -66,13 +99,14 @@ title: Cool video games
 ---
 # Hello, friend!"
 				.to_string(),
-		);
+		)
+		.unwrap();
 
 		assert_eq!(
 			page.metadata.unwrap().get("title"),
 			Some(&"Cool video games".to_string())
 		);
-		assert_eq!(page.content, "<h1>Hello, friend!</h1>\n");
+		assert_eq!(page.content, HELLO_FRIEND);
 	}
 
 	#[test]
@@ -89,9 +123,10 @@ title: Cool video games
 ---
 # Hello, friend!"
 				.to_string(),
-		);
+		)
+		.unwrap();
 
 		assert_eq!(page.metadata.unwrap().title, "Cool video games".to_string());
-		assert_eq!(page.content, "<h1>Hello, friend!</h1>\n");
+		assert_eq!(page.content, HELLO_FRIEND);
 	}
 }
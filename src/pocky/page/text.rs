@@ -2,51 +2,61 @@ use serde::de::DeserializeOwned;
 use std::fs;
 use std::path::Path;
 
-use crate::AsHtml;
+use crate::pocky::frontmatter;
+use crate::pocky::AsHtml;
+use crate::pocky::BuildError;
+use crate::pocky::FromFile;
 
 #[derive(Clone, Debug)]
 pub struct TextPage<M: DeserializeOwned> {
 	pub metadata: Option<M>,
 	pub content: String,
+	/// Problems that don't stop the page from being built, like unknown frontmatter
+	/// keys. It's up to the caller to decide how seriously to take them.
+	pub warnings: Vec<BuildError>,
 }
 
 impl<M: DeserializeOwned> TextPage<M> {
-	pub fn parse(content: String) -> Self {
-		// Skip blank lines from the beginning
-		let mut lines = content
-			.lines()
-			.skip_while(|line| line.trim().is_empty())
-			.peekable();
-
-		let is_frontmatter_delimiter =
-			|line: &&str| line.len() >= 3 && line.find(|c| c != '-').is_none();
+	pub fn parse(content: String) -> Result<Self, BuildError> {
+		let (frontmatter, lines) = frontmatter::split(&content)?;
 
 		// Parse the frontmatter section, if the document starts with one
-		let metadata = lines.next_if(is_frontmatter_delimiter).map(|_| {
-			let metadata_source = lines
-				.by_ref()
-				.take_while(|line| !is_frontmatter_delimiter(line))
-				.map(|line| format!("{}\n", line))
-				.collect::<String>();
-
-			serde_yaml::from_str(&metadata_source).expect("failed to deserialize frontmatter metadata")
-		});
+		let (metadata, warnings) = match frontmatter {
+			Some(frontmatter) => {
+				let (metadata, warnings) = frontmatter.deserialize()?;
+				(Some(metadata), warnings)
+			}
+			None => (None, Vec::new()),
+		};
 
 		// The remaining lines are the actual document content
-		let content = lines.map(|line| format!("{}\n", line)).collect::<String>();
+		let content = lines
+			.into_iter()
+			.map(|line| format!("{}\n", line))
+			.collect::<String>();
 
-		TextPage { metadata, content }
+		Ok(TextPage {
+			metadata,
+			content,
+			warnings,
+		})
 	}
 }
 
-impl<M, P> From<P> for TextPage<M>
+impl<M> FromFile for TextPage<M>
 where
-	P: AsRef<Path>,
 	M: DeserializeOwned,
 {
-	fn from(path: P) -> Self {
-		let content = fs::read_to_string(path).expect("unable to read file");
-		TextPage::parse(content)
+	fn from_file(path: &Path) -> Result<Self, BuildError> {
+		let content = fs::read_to_string(path)
+			.map_err(|err| BuildError::new(format!("unable to read file: {}", err)).in_file(path))?;
+		let mut page = TextPage::parse(content).map_err(|err| err.in_file(path))?;
+		page.warnings = page
+			.warnings
+			.into_iter()
+			.map(|warning| warning.in_file(path))
+			.collect();
+		Ok(page)
 	}
 }
 
@@ -58,3 +68,114 @@ where
 		self.content.clone()
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde::Deserialize;
+
+	use crate::pocky::de;
+
+	#[derive(Debug, Deserialize)]
+	#[allow(dead_code)]
+	struct DateMetadata {
+		#[serde(deserialize_with = "de::date")]
+		date: chrono::NaiveDate,
+	}
+
+	#[test]
+	fn error_location() {
+		let error = TextPage::<DateMetadata>::parse(
+			"
+---
+title: Cool video games
+date: tomorrow
+---
+# Hello, friend!"
+				.to_string(),
+		)
+		.unwrap_err();
+
+		assert_eq!(error.line, Some(4));
+		assert!(error.message.contains("invalid date `tomorrow`"));
+	}
+
+	#[derive(Debug, Deserialize)]
+	struct ImportedMetadata {
+		title: String,
+		#[serde(deserialize_with = "de::date")]
+		date: chrono::NaiveDate,
+		#[serde(default, deserialize_with = "de::comma_separated")]
+		tags: Vec<String>,
+	}
+
+	#[test]
+	fn toml_frontmatter() {
+		let page = TextPage::<ImportedMetadata>::parse(
+			"+++\ntitle = \"Cool video games\"\ndate = 2023-07-15\ntags = [\"games\", \"rust\"]\n+++\n# Hello, friend!\n"
+				.to_string(),
+		)
+		.unwrap();
+		let metadata = page.metadata.unwrap();
+
+		assert_eq!(metadata.title, "Cool video games");
+		assert_eq!(metadata.date.to_string(), "2023-07-15");
+		assert_eq!(metadata.tags, vec!["games", "rust"]);
+		assert_eq!(page.content, "# Hello, friend!\n");
+
+		let page = TextPage::<ImportedMetadata>::parse(
+			"+++\ntitle = \"Cool video games\"\ndate = 2023-07-15T09:30:00Z\n+++\n".to_string(),
+		)
+		.unwrap();
+		assert_eq!(page.metadata.unwrap().date.to_string(), "2023-07-15");
+	}
+
+	#[test]
+	fn json_frontmatter() {
+		let page = TextPage::<ImportedMetadata>::parse(
+			"{\n  \"title\": \"Cool video games\",\n  \"date\": \"2023.7.15\"\n}\n# Hello, friend!\n"
+				.to_string(),
+		)
+		.unwrap();
+
+		assert_eq!(page.metadata.unwrap().title, "Cool video games");
+		assert_eq!(page.content, "# Hello, friend!\n");
+
+		let error = TextPage::<ImportedMetadata>::parse(
+			"{\n  \"title\": \"Cool video games\",\n  \"date\": \"soon\"\n}\n".to_string(),
+		)
+		.unwrap_err();
+		assert_eq!(error.line, Some(3));
+	}
+
+	#[test]
+	fn slide_break_is_not_frontmatter() {
+		let page =
+			TextPage::<ImportedMetadata>::parse("+++\n# First slide\n+++\n# Second slide\n".to_string())
+				.unwrap();
+
+		assert!(page.metadata.is_none());
+		assert_eq!(page.content, "+++\n# First slide\n+++\n# Second slide\n");
+	}
+
+	#[test]
+	fn unknown_field_warnings() {
+		#[derive(Deserialize)]
+		#[allow(dead_code)]
+		struct SummaryMetadata {
+			summary: Option<String>,
+		}
+
+		let page = TextPage::<SummaryMetadata>::parse(
+			"---\ndescription: Cool video games\n---\n# Hello, friend!".to_string(),
+		)
+		.unwrap();
+
+		assert_eq!(page.warnings.len(), 1);
+		assert_eq!(page.warnings[0].line, Some(2));
+		assert_eq!(
+			page.warnings[0].hint.as_deref(),
+			Some("did you mean `summary`?")
+		);
+	}
+}
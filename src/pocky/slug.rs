@@ -0,0 +1,22 @@
+/// Turns a title like "Cool video games!" into a slug like "cool-video-games", which
+/// is safe to use in file names and urls
+pub fn slugify(title: &str) -> String {
+	title
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(|word| word.to_lowercase())
+		.collect::<Vec<_>>()
+		.join("-")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn slugify_titles() {
+		assert_eq!(slugify("Cool video games!"), "cool-video-games");
+		assert_eq!(slugify("A tale of two Clang"), "a-tale-of-two-clang");
+		assert_eq!(slugify("  --  "), "");
+	}
+}
@@ -0,0 +1,112 @@
+use handlebars::Handlebars;
+use once_cell::sync::Lazy;
+use resvg::tiny_skia;
+use resvg::usvg;
+use serde_json::json;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::blog_post::BlogPost;
+use crate::config::BLOG;
+use crate::meta::cover_image;
+
+/// The size that most sites expect link preview images to be
+pub const WIDTH: u32 = 1200;
+pub const HEIGHT: u32 = 630;
+
+/// Roughly how many characters of the title fit on one line of the image
+const LINE_LENGTH: usize = 24;
+const MAX_LINES: usize = 4;
+
+const DEFAULT_ACCENT_COLOR: &str = "#d178d3";
+
+/// The fonts are bundled, rather than loaded from the system, so that the images come
+/// out the same no matter where the site is built
+static FONTS: Lazy<Arc<usvg::fontdb::Database>> = Lazy::new(|| {
+	let mut fonts = usvg::fontdb::Database::new();
+	fonts.load_font_data(include_bytes!("./fonts/DejaVuSans.ttf").to_vec());
+	fonts.load_font_data(include_bytes!("./fonts/DejaVuSans-Bold.ttf").to_vec());
+	fonts.set_sans_serif_family("DejaVu Sans");
+	Arc::new(fonts)
+});
+
+/// Posts with a cover use it for link previews, so they don't need one generated
+pub fn has_preview(post: &BlogPost) -> bool {
+	cover_image(&post.metadata.cover).is_none()
+}
+
+/// Where the preview image for the post at `path` ends up, like
+/// `og/cool-video-games.png`
+pub fn preview_path(path: &Path) -> PathBuf {
+	let name = path.file_stem().expect("post should have a file name");
+	PathBuf::from("og/").join(name).with_extension("png")
+}
+
+/// Renders the link preview image for a post, as a png
+pub fn render_preview(post: &BlogPost) -> Vec<u8> {
+	let metadata = &post.metadata;
+	let title_lines = wrap(&metadata.title);
+	// Keep the title roughly centered in the space above the byline
+	let title_y = 280 - (title_lines.len() as i32 - 1) * 43;
+
+	let svg = Handlebars::new()
+		.render_template(
+			include_str!("./templates/preview.svg"),
+			&json!({
+				"title_lines": title_lines,
+				"title_y": title_y,
+				"author": &metadata.author,
+				"date": metadata.date.map(|date| date.format("%B %-d, %Y").to_string()),
+				"accent_color": metadata.accent_color.as_deref().unwrap_or(DEFAULT_ACCENT_COLOR),
+				"site": &BLOG.og_title,
+			}),
+		)
+		.expect("failed to render handlebars");
+
+	let options = usvg::Options {
+		fontdb: FONTS.clone(),
+		..Default::default()
+	};
+	let tree = usvg::Tree::from_str(&svg, &options).expect("invalid preview image template");
+	let mut pixmap = tiny_skia::Pixmap::new(WIDTH, HEIGHT).expect("invalid preview image size");
+	resvg::render(&tree, tiny_skia::Transform::default(), &mut pixmap.as_mut());
+	pixmap.encode_png().expect("failed to encode preview image")
+}
+
+/// SVG doesn't wrap text on its own, so we split the title into lines ourselves. If
+/// the title is too long to fit, the last line gets cut short.
+fn wrap(title: &str) -> Vec<String> {
+	let mut lines = Vec::<String>::new();
+	for word in title.split_whitespace() {
+		match lines.last_mut() {
+			Some(line) if line.chars().count() + word.chars().count() < LINE_LENGTH => {
+				line.push(' ');
+				line.push_str(word);
+			}
+			_ => lines.push(word.to_string()),
+		}
+	}
+
+	if lines.len() > MAX_LINES {
+		lines.truncate(MAX_LINES);
+		lines[MAX_LINES - 1].push('\u{2026}');
+	}
+
+	lines
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn wraps_titles() {
+		assert_eq!(wrap("Cool video games"), vec!["Cool video games"]);
+		assert_eq!(
+			wrap("Quit using AI to fill out your blog posts"),
+			vec!["Quit using AI to fill", "out your blog posts"]
+		);
+		assert_eq!(wrap(&"word ".repeat(40)).len(), MAX_LINES);
+	}
+}
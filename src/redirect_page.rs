@@ -1,15 +1,18 @@
 use handlebars::Handlebars;
+use serde::Deserialize;
 use serde::Serialize;
+use serde_json::json;
 use std::hash::Hash;
 use std::hash::Hasher;
 use std::path::PathBuf;
 
+use crate::config::BLOG;
 use crate::pocky::AsHtml;
 
-#[derive(Debug, Eq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, Serialize)]
 pub struct RedirectPage {
 	pub from: PathBuf,
-	pub to: &'static str,
+	pub to: String,
 }
 
 impl PartialEq for RedirectPage {
@@ -31,7 +34,13 @@ impl AsHtml for RedirectPage {
 	fn as_html(&self) -> String {
 		let renderer = Handlebars::new();
 		renderer
-			.render_template(include_str!("./templates/redirect.html"), self)
+			.render_template(
+				include_str!("./templates/redirect.html"),
+				&json!({
+					"blog": &*BLOG,
+					"to": &self.to,
+				}),
+			)
 			.expect("failed to render handlebars")
 	}
 }
@@ -43,6 +52,8 @@ macro_rules! redirect_config {
     use std::collections::HashSet;
 
     Lazy::new(|| {
+      // Mutability goes unused when there aren't any redirects
+      #[allow(unused_mut)]
       let mut redirects = HashSet::default();
       $({
         redirects.insert(RedirectPage {
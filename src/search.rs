@@ -0,0 +1,147 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::blog_post::BlogPost;
+use crate::blog_post::BlogPostStatus::Published;
+use crate::pocky::md;
+use crate::talk::Talk;
+
+/// How much a single occurrence of a word counts for, depending on where it appears
+const TITLE_WEIGHT: u32 = 10;
+const TAG_WEIGHT: u32 = 5;
+const SUMMARY_WEIGHT: u32 = 3;
+const BODY_WEIGHT: u32 = 1;
+
+/// Words that show up in nearly every post, and would only bloat the index
+pub const STOP_WORDS: &[&str] = &[
+	"a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+	"its", "of", "on", "or", "so", "that", "the", "their", "then", "there", "these", "they", "this",
+	"to", "was", "we", "were", "will", "with", "you",
+];
+
+/// An inverted index of every published post and talk, small enough for `/search.html`
+/// to download and query entirely in the browser
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SearchIndex {
+	pub documents: Vec<SearchDocument>,
+	/// Maps each word to the documents that contain it, as `[document, score]` pairs
+	pub terms: BTreeMap<String, Vec<(usize, u32)>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchDocument {
+	pub title: String,
+	pub url: String,
+	pub date: Option<String>,
+	pub summary: Option<String>,
+}
+
+/// Splits text into lowercase words, leaving out anything too common to be useful
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+	text
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|word| word.chars().count() > 1)
+		.map(str::to_lowercase)
+		.filter(|word| !STOP_WORDS.contains(&word.as_str()))
+}
+
+impl SearchIndex {
+	pub fn new<'a, P, T>(posts: P, talks: T) -> Self
+	where
+		P: IntoIterator<Item = &'a BlogPost>,
+		T: IntoIterator<Item = &'a Talk>,
+	{
+		let mut index = SearchIndex::default();
+
+		// Unlisted and draft posts shouldn't be findable
+		for post in posts
+			.into_iter()
+			.filter(|post| post.metadata.status == Published)
+		{
+			let metadata = &post.metadata;
+			let summary = post.excerpt.as_ref().map(|excerpt| excerpt.text.clone());
+			index.add(
+				SearchDocument {
+					title: metadata.title.clone(),
+					url: post.canonical_url.to_string(),
+					date: metadata.date.map(|date| date.to_string()),
+					summary: summary.clone(),
+				},
+				&[
+					(&metadata.title, TITLE_WEIGHT),
+					(&metadata.tags.join(" "), TAG_WEIGHT),
+					(&summary.unwrap_or_default(), SUMMARY_WEIGHT),
+					(&md::html_to_text(&post.content), BODY_WEIGHT),
+				],
+			);
+		}
+
+		// The slides of upcoming talks aren't ready to be found yet
+		for talk in talks.into_iter().filter(|talk| !talk.upcoming) {
+			let metadata = &talk.metadata;
+			let summary = metadata.summary.as_deref().map(md::html_to_text);
+			index.add(
+				SearchDocument {
+					title: metadata.title.clone(),
+					url: talk.canonical_url.to_string(),
+					date: Some(metadata.date.to_string()),
+					summary: summary.clone(),
+				},
+				&[
+					(&metadata.title, TITLE_WEIGHT),
+					(&metadata.tags.join(" "), TAG_WEIGHT),
+					(&summary.unwrap_or_default(), SUMMARY_WEIGHT),
+					(&md::html_to_text(&talk.content()), BODY_WEIGHT),
+				],
+			);
+		}
+
+		index
+	}
+
+	fn add(&mut self, document: SearchDocument, fields: &[(&String, u32)]) {
+		let id = self.documents.len();
+		self.documents.push(document);
+
+		let mut scores = BTreeMap::<String, u32>::new();
+		for (text, weight) in fields {
+			for word in words(text) {
+				*scores.entry(word).or_default() += weight;
+			}
+		}
+		for (word, score) in scores {
+			self.terms.entry(word).or_default().push((id, score));
+		}
+	}
+
+	pub fn to_json(&self) -> String {
+		serde_json::to_string(self).expect("failed to serialize search index")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn indexes_words() {
+		let mut index = SearchIndex::default();
+		let title = "Cool video games".to_string();
+		let body = md::html_to_text("<p>I like the games &amp; the music</p>");
+		index.add(
+			SearchDocument {
+				title: title.clone(),
+				url: "https://mckayla.blog/posts/cool-video-games.html".to_string(),
+				date: None,
+				summary: None,
+			},
+			&[(&title, TITLE_WEIGHT), (&body, BODY_WEIGHT)],
+		);
+
+		assert_eq!(index.terms["games"], vec![(0, TITLE_WEIGHT + BODY_WEIGHT)]);
+		assert_eq!(index.terms["music"], vec![(0, BODY_WEIGHT)]);
+		assert!(!index.terms.contains_key("the"));
+		assert!(!index.terms.contains_key("amp"));
+		assert!(!index.terms.contains_key("i"));
+	}
+}
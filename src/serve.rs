@@ -0,0 +1,296 @@
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// The endpoint that open pages listen on for reload events
+const RELOAD_PATH: &str = "/__reload";
+
+/// Injected into every html page we serve, but never written to the output directory
+const RELOAD_SCRIPT: &str = r#"<script>
+new EventSource("/__reload").addEventListener("reload", () => location.reload());
+</script>"#;
+
+/// Lets the build tell every open browser tab that the site has changed. Each
+/// connected tab waits for the generation to change, and reloads when it does.
+#[derive(Clone, Debug, Default)]
+pub struct Reloader {
+	generation: Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl Reloader {
+	pub fn reload(&self) {
+		let (generation, changed) = &*self.generation;
+		*generation.lock().unwrap() += 1;
+		changed.notify_all();
+	}
+
+	fn current(&self) -> u64 {
+		*self.generation.0.lock().unwrap()
+	}
+
+	/// Blocks until the generation moves past `seen`, or until the timeout elapses.
+	/// Returns the latest generation.
+	fn wait(&self, seen: u64, timeout: Duration) -> u64 {
+		let (generation, changed) = &*self.generation;
+		let (generation, _) = changed
+			.wait_timeout_while(generation.lock().unwrap(), timeout, |generation| {
+				*generation == seen
+			})
+			.unwrap();
+		*generation
+	}
+}
+
+/// A tiny static file server for previewing the output directory locally. It resolves
+/// paths the same way our static host does in production, so `/` serves `index.html`,
+/// and a directory path like `/talks/` serves `/talks/index.html`.
+pub fn serve<P>(root: P, port: u16, reloader: Reloader) -> io::Result<()>
+where
+	P: AsRef<Path>,
+{
+	let root = root.as_ref().to_owned();
+	let listener = TcpListener::bind(("127.0.0.1", port))?;
+	println!("serving {} at http://localhost:{}/", root.display(), port);
+
+	for stream in listener.incoming().flatten() {
+		let root = root.clone();
+		let reloader = reloader.clone();
+		thread::spawn(move || {
+			if let Err(err) = handle_connection(stream, &root, &reloader) {
+				eprintln!("error: failed to respond to request: {}", err);
+			}
+		});
+	}
+
+	Ok(())
+}
+
+fn handle_connection(mut stream: TcpStream, root: &Path, reloader: &Reloader) -> io::Result<()> {
+	let mut request_line = String::new();
+	BufReader::new(&stream).read_line(&mut request_line)?;
+
+	let mut parts = request_line.split_whitespace();
+	let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+		return respond(
+			&mut stream,
+			"400 Bad Request",
+			"text/plain",
+			b"bad request",
+			false,
+		);
+	};
+	let head = method == "HEAD";
+
+	if method != "GET" && method != "HEAD" {
+		return respond(
+			&mut stream,
+			"405 Method Not Allowed",
+			"text/plain",
+			b"method not allowed",
+			false,
+		);
+	}
+
+	if target == RELOAD_PATH {
+		return stream_reloads(&mut stream, reloader);
+	}
+
+	match resolve(root, target).and_then(|path| Some((fs::read(&path).ok()?, path))) {
+		Some((mut body, path)) => {
+			if path.extension().is_some_and(|ext| ext == "html") {
+				body = inject_reload_script(body);
+			}
+
+			respond(&mut stream, "200 OK", content_type(&path), &body, head)
+		}
+		None => respond(
+			&mut stream,
+			"404 Not Found",
+			"text/plain",
+			b"not found",
+			head,
+		),
+	}
+}
+
+/// Holds the connection open as a server-sent event stream, and sends a `reload`
+/// event whenever the site is rebuilt. The periodic comments let us notice when the
+/// tab has gone away, since writing to a closed connection will fail.
+fn stream_reloads(stream: &mut TcpStream, reloader: &Reloader) -> io::Result<()> {
+	write!(
+		stream,
+		"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-store\r\n\r\n"
+	)?;
+	stream.flush()?;
+
+	let mut seen = reloader.current();
+	loop {
+		let generation = reloader.wait(seen, Duration::from_secs(15));
+		if generation == seen {
+			write!(stream, ": keep-alive\n\n")?;
+		} else {
+			seen = generation;
+			write!(stream, "event: reload\ndata: {}\n\n", generation)?;
+		}
+		stream.flush()?;
+	}
+}
+
+fn inject_reload_script(body: Vec<u8>) -> Vec<u8> {
+	match String::from_utf8(body) {
+		Ok(mut html) => {
+			let index = html.rfind("</body>").unwrap_or(html.len());
+			html.insert_str(index, RELOAD_SCRIPT);
+			html.into_bytes()
+		}
+		Err(err) => err.into_bytes(),
+	}
+}
+
+/// Maps a request target onto a file in the output directory, refusing anything that
+/// would escape it.
+fn resolve(root: &Path, target: &str) -> Option<PathBuf> {
+	let path = target.split(['?', '#']).next().unwrap_or("/");
+	let path = percent_decode(path.strip_prefix('/')?)?;
+	// Joining an absolute path would replace the root entirely, so anything that
+	// isn't a plain relative path (like `//etc/hostname` or `%2F..`) gets refused
+	if path.starts_with(['/', '\\'])
+		|| !Path::new(&path)
+			.components()
+			.all(|component| matches!(component, Component::Normal(_)))
+	{
+		return None;
+	}
+
+	let mut file = root.join(&path);
+	if path.is_empty() || path.ends_with('/') || file.is_dir() {
+		file = file.join("index.html");
+	}
+
+	file.is_file().then_some(file)
+}
+
+/// Decodes the `%20`-style escapes in a path, so that files with spaces or non-ascii
+/// characters in their names can be found. Returns `None` for malformed escapes, or
+/// if the result isn't valid utf-8.
+fn percent_decode(path: &str) -> Option<String> {
+	let mut bytes = Vec::with_capacity(path.len());
+	let mut rest = path.as_bytes();
+	while let Some((&byte, tail)) = rest.split_first() {
+		if byte == b'%' {
+			let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+			bytes.push(u8::from_str_radix(hex, 16).ok()?);
+			rest = &tail[2..];
+		} else {
+			bytes.push(byte);
+			rest = tail;
+		}
+	}
+
+	String::from_utf8(bytes).ok()
+}
+
+fn content_type(path: &Path) -> &'static str {
+	match path.extension().and_then(|ext| ext.to_str()) {
+		Some("html") => "text/html; charset=utf-8",
+		Some("css") => "text/css; charset=utf-8",
+		Some("js") => "text/javascript; charset=utf-8",
+		Some("json") => "application/json",
+		Some("xml") => "application/xml",
+		Some("txt") => "text/plain; charset=utf-8",
+		Some("ics") => "text/calendar; charset=utf-8",
+		Some("svg") => "image/svg+xml",
+		Some("png") => "image/png",
+		Some("avif") => "image/avif",
+		Some("webp") => "image/webp",
+		Some("jpg" | "jpeg") => "image/jpeg",
+		_ => "application/octet-stream",
+	}
+}
+
+fn respond(
+	stream: &mut TcpStream,
+	status: &str,
+	content_type: &str,
+	body: &[u8],
+	// Responses to HEAD requests describe the body, but don't include it
+	head: bool,
+) -> io::Result<()> {
+	write!(
+		stream,
+		"HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+		status,
+		content_type,
+		body.len()
+	)?;
+	if !head {
+		stream.write_all(body)?;
+	}
+	stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decodes_paths() {
+		assert_eq!(
+			percent_decode("posts/cool%20video%20games%C3%A9.html").as_deref(),
+			Some("posts/cool video gamesé.html")
+		);
+		assert_eq!(
+			percent_decode("%2e%2e/secret").as_deref(),
+			Some("../secret")
+		);
+		assert_eq!(percent_decode("bad%2"), None);
+		assert_eq!(percent_decode("bad%zz"), None);
+	}
+
+	#[test]
+	fn resolves_paths_inside_root() {
+		let root = std::env::temp_dir().join(format!("serve-resolve-{}", std::process::id()));
+		fs::create_dir_all(root.join("talks")).unwrap();
+		fs::write(root.join("index.html"), "").unwrap();
+		fs::write(root.join("talks/index.html"), "").unwrap();
+		fs::write(root.join("cool games.html"), "").unwrap();
+
+		assert_eq!(resolve(&root, "/"), Some(root.join("index.html")));
+		assert_eq!(
+			resolve(&root, "/talks/"),
+			Some(root.join("talks/index.html"))
+		);
+		assert_eq!(
+			resolve(&root, "/talks"),
+			Some(root.join("talks/index.html"))
+		);
+		assert_eq!(
+			resolve(&root, "/cool%20games.html?ref=feed"),
+			Some(root.join("cool games.html"))
+		);
+		assert_eq!(resolve(&root, "/missing.html"), None);
+
+		// Absolute paths and parent directories would escape the root
+		assert_eq!(resolve(&root, "//etc/hostname"), None);
+		assert_eq!(resolve(&root, "/%2Fetc/hostname"), None);
+		assert_eq!(resolve(&root, "/%5Cetc/hostname"), None);
+		assert_eq!(resolve(&root, "/../index.html"), None);
+		assert_eq!(resolve(&root, "/talks/%2e%2e/%2e%2e/index.html"), None);
+		assert_eq!(resolve(&root, "/./index.html"), None);
+		assert_eq!(resolve(&root, "index.html"), None);
+
+		fs::remove_dir_all(&root).unwrap();
+	}
+}
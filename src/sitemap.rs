@@ -0,0 +1,69 @@
+use chrono::NaiveDate;
+use url::Url;
+
+use crate::pocky::escape::escape;
+
+/// A sitemap, as described at https://www.sitemaps.org/protocol.html
+#[derive(Clone, Debug, Default)]
+pub struct Sitemap {
+	pub urls: Vec<SitemapUrl>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SitemapUrl {
+	pub loc: String,
+	pub lastmod: Option<NaiveDate>,
+}
+
+impl Sitemap {
+	pub fn add<L>(&mut self, loc: L, lastmod: Option<NaiveDate>)
+	where
+		L: ToString,
+	{
+		self.urls.push(SitemapUrl {
+			loc: loc.to_string(),
+			lastmod,
+		});
+	}
+
+	pub fn to_xml(&self) -> String {
+		let mut xml = String::new();
+		xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+		xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+		for url in self.urls.iter() {
+			xml.push_str("\t<url>\n");
+			xml.push_str(&format!("\t\t<loc>{}</loc>\n", escape(&url.loc)));
+			if let Some(lastmod) = url.lastmod {
+				xml.push_str(&format!("\t\t<lastmod>{}</lastmod>\n", lastmod));
+			}
+			xml.push_str("\t</url>\n");
+		}
+		xml.push_str("</urlset>\n");
+		xml
+	}
+}
+
+/// Lets every crawler in, and tells them where to find the sitemap
+pub fn robots_txt(sitemap_url: &Url) -> String {
+	format!("User-agent: *\nAllow: /\n\nSitemap: {}\n", sitemap_url)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn writes_urls() {
+		let mut sitemap = Sitemap::default();
+		sitemap.add("https://mckayla.blog/", None);
+		sitemap.add(
+			"https://mckayla.blog/posts/cool-video-games.html?a=1&b=2",
+			NaiveDate::from_ymd_opt(2023, 7, 15),
+		);
+		let xml = sitemap.to_xml();
+
+		assert!(xml.contains("\t<url>\n\t\t<loc>https://mckayla.blog/</loc>\n\t</url>\n"));
+		assert!(xml.contains("<loc>https://mckayla.blog/posts/cool-video-games.html?a=1&amp;b=2</loc>"));
+		assert!(xml.contains("<lastmod>2023-07-15</lastmod>"));
+	}
+}
@@ -0,0 +1,174 @@
+use handlebars::Handlebars;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::pocky::de;
+use crate::pocky::frontmatter::without_location;
+use crate::pocky::md;
+use crate::pocky::md::TocEntry;
+
+/// A single slide of a talk, after the title slide
+#[derive(Clone, Debug, Serialize, Eq, PartialEq)]
+pub struct Slide {
+	/// Where the slide is in the talk, counting the title slide as 0
+	pub number: usize,
+	#[serde(flatten)]
+	pub attributes: SlideAttributes,
+	pub content: String,
+	/// The markdown that `content` was rendered from, without the speaker notes
+	#[serde(skip)]
+	pub source: String,
+	/// The speaker notes, from the html comments in the slide
+	pub notes: Option<String>,
+}
+
+/// Set on the line that starts a slide, like `+++ {layout: title, class: center}`
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlideAttributes {
+	pub layout: SlideLayout,
+	/// Extra classes for the slide, to be styled by talk.css
+	#[serde(deserialize_with = "de::comma_separated")]
+	pub class: Vec<String>,
+	/// An image that fills the whole slide
+	pub background: Option<String>,
+	pub transition: Option<SlideTransition>,
+}
+
+/// Which partial the slide is rendered with. Each of these has a template in
+/// templates/slides/.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SlideLayout {
+	#[default]
+	Default,
+	/// A big, centered heading, for introducing a new section of the talk
+	Title,
+	/// Puts the content over the background image, rather than in the middle of it
+	Image,
+	/// Splits the content into two columns
+	Columns,
+}
+
+/// How the slide appears when it's navigated to
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SlideTransition {
+	None,
+	Fade,
+	Slide,
+	Zoom,
+}
+
+/// Makes every layout available to `renderer` as a partial, named after the layout
+pub fn register_layouts(renderer: &mut Handlebars) {
+	for (name, template) in [
+		("slide", include_str!("./templates/slides/slide.html")),
+		("default", include_str!("./templates/slides/default.html")),
+		("title", include_str!("./templates/slides/title.html")),
+		("image", include_str!("./templates/slides/image.html")),
+		("columns", include_str!("./templates/slides/columns.html")),
+	] {
+		renderer
+			.register_partial(name, template)
+			.expect("failed to register handlebars partial");
+	}
+}
+
+/// If the line starts a new slide, returns its attributes, which might be empty
+fn separator(line: &str) -> Option<&str> {
+	let attributes = line.trim_end().strip_prefix("+++")?.trim_start();
+	(attributes.is_empty() || attributes.starts_with('{')).then_some(attributes)
+}
+
+/// Splits the content of a talk into slides, on lines that start with `+++`. Each
+/// slide gets its own speaker notes, and an entry in the table of contents if it
+/// starts a new heading.
+pub fn split_slides(content: &str) -> Result<(Vec<Slide>, Vec<TocEntry>), String> {
+	// The first slide doesn't need a separator, but it can still have one to set its
+	// attributes
+	let mut chunks = vec![("", String::new())];
+	for line in content.lines() {
+		match separator(line) {
+			Some(attributes) if chunks.len() == 1 && chunks[0].1.trim().is_empty() => {
+				chunks[0] = (attributes, String::new());
+			}
+			Some(attributes) => chunks.push((attributes, String::new())),
+			None => {
+				let (_, source) = chunks.last_mut().unwrap();
+				source.push_str(line);
+				source.push('\n');
+			}
+		}
+	}
+
+	let mut slides = Vec::new();
+	let mut toc = Vec::<TocEntry>::new();
+	for (index, (attributes, source)) in chunks.into_iter().enumerate() {
+		let number = index + 1;
+		let attributes = match attributes {
+			"" => SlideAttributes::default(),
+			attributes => serde_yaml::from_str(attributes).map_err(|err| {
+				format!(
+					"invalid attributes for slide {}: {}",
+					number,
+					without_location(&err.to_string())
+				)
+			})?,
+		};
+
+		let (source, comments) = md::split_comments(&source);
+		let document = md::render(&source, false, None);
+
+		// Slides that continue the one before them usually repeat its heading
+		if let Some(heading) = document.toc.into_iter().next() {
+			if toc.last().map(|entry| &entry.title) != Some(&heading.title) {
+				toc.push(TocEntry {
+					level: 1,
+					id: format!("slide-{}", number),
+					title: heading.title,
+					children: Vec::new(),
+				});
+			}
+		}
+
+		slides.push(Slide {
+			number,
+			attributes,
+			content: document.html,
+			source,
+			notes: (!comments.is_empty()).then(|| md::markdown_to_html(comments.join("\n\n"))),
+		});
+	}
+
+	Ok((slides, toc))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn slide_attributes() {
+		let (slides, toc) = split_slides(
+			"+++ {layout: title}\n# Intro\n+++\n# Intro\n<!-- again -->\n+++ {class: [center, big], background: https://cdn.mckayla.cloud/cover.avif, transition: fade}\nHi\n",
+		)
+		.unwrap();
+
+		assert_eq!(slides.len(), 3);
+		assert_eq!(slides[0].attributes.layout, SlideLayout::Title);
+		assert_eq!(slides[1].attributes, SlideAttributes::default());
+		assert_eq!(slides[1].notes.as_deref(), Some("<p>again</p>\n"));
+		assert_eq!(slides[2].number, 3);
+		assert_eq!(slides[2].attributes.class, vec!["center", "big"]);
+		assert_eq!(
+			slides[2].attributes.background.as_deref(),
+			Some("https://cdn.mckayla.cloud/cover.avif")
+		);
+		assert_eq!(slides[2].attributes.transition, Some(SlideTransition::Fade));
+		assert_eq!(toc.len(), 1);
+
+		assert!(split_slides("+++ {layout: nope}\n").is_err());
+		assert!(split_slides("+++ {colour: red}\n").is_err());
+	}
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::index_entry::IndexEntry;
+use crate::pocky::slug::slugify;
+
+/// Everything on the site that shares a tag
+#[derive(Clone, Debug, Serialize)]
+pub struct Tag<'a> {
+	/// The tag as it was first written, like "Rust"
+	pub name: &'a str,
+	/// The tag as it appears in urls, like "rust"
+	pub slug: String,
+	pub entries: Vec<&'a IndexEntry>,
+	/// How many entries have this tag, since templates can't count for themselves
+	pub count: usize,
+}
+
+impl<'a> Tag<'a> {
+	pub fn path(&self) -> PathBuf {
+		PathBuf::from(format!("tags/{}.html", self.slug))
+	}
+
+	pub fn feed_path(&self) -> PathBuf {
+		PathBuf::from(format!("tags/{}.xml", self.slug))
+	}
+}
+
+/// Groups entries by tag. Tags are matched by their slug, so "Rust" and "rust" end up
+/// on the same page. Entries keep the order they're given in.
+pub fn tags<'a, I>(entries: I) -> Vec<Tag<'a>>
+where
+	I: IntoIterator<Item = &'a IndexEntry>,
+{
+	let mut tags = BTreeMap::<String, Tag>::new();
+
+	for entry in entries {
+		for name in entry.tags() {
+			let slug = slugify(name);
+			if slug.is_empty() {
+				continue;
+			}
+
+			let tag = tags.entry(slug.clone()).or_insert_with(|| Tag {
+				name,
+				slug,
+				entries: Vec::new(),
+				count: 0,
+			});
+			// Don't list an entry twice if it has the same tag written two ways
+			if !tag.entries.iter().any(|it| std::ptr::eq(*it, entry)) {
+				tag.entries.push(entry);
+				tag.count += 1;
+			}
+		}
+	}
+
+	tags.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	fn external(title: &str, tags: &str) -> IndexEntry {
+		IndexEntry::External(
+			serde_json::from_value(json!({
+				"canonical_url": "https://example.com/",
+				"path": "https://example.com/",
+				"title": title,
+				"author": "Kayla Washburn",
+				"date": "2023.7.15",
+				"tags": tags,
+			}))
+			.unwrap(),
+		)
+	}
+
+	#[test]
+	fn groups_tags_by_slug() {
+		let entries = [external("Cool", "Rust, games"), external("Neat", "rust")];
+		let tags = tags(&entries);
+
+		assert_eq!(tags.len(), 2);
+		assert_eq!(tags[1].name, "Rust");
+		assert_eq!(tags[1].slug, "rust");
+		assert_eq!(tags[1].count, 2);
+	}
+}
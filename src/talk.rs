@@ -1,24 +1,46 @@
 use chrono::NaiveDate;
-use handlebars::handlebars_helper;
+use chrono::NaiveTime;
+use chrono_tz::Tz;
 use handlebars::Handlebars;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_json::json;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
+use url::Url;
 
+use crate::config;
+use crate::config::BLOG;
+use crate::index_entry::SortKey;
+use crate::meta::Meta;
 use crate::pocky::de;
 use crate::pocky::md;
+use crate::pocky::md::TocEntry;
 use crate::pocky::ser;
 use crate::pocky::AsHtml;
+use crate::pocky::BuildError;
+use crate::pocky::FromFile;
 use crate::pocky::TextPage;
+use crate::slide;
+use crate::slide::Slide;
 
 #[derive(Clone, Debug, Serialize, Eq, PartialEq)]
 pub struct Talk {
+	pub canonical_url: Url,
 	pub path: PathBuf,
 	#[serde(flatten)]
 	pub metadata: TalkMetadata,
-	pub content: Vec<String>,
+	pub slides: Vec<Slide>,
+	/// The first heading of each slide, linking to the slide, for the handout
+	pub toc: Vec<TocEntry>,
+	/// Including the title slide
+	pub slide_count: usize,
+	/// Whether the talk hasn't been given yet, in which case its slides probably
+	/// aren't finished either
+	pub upcoming: bool,
+	#[serde(skip)]
+	pub warnings: Vec<BuildError>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
@@ -29,48 +51,190 @@ pub struct TalkMetadata {
 	pub author: String,
 	#[serde(deserialize_with = "de::date", serialize_with = "ser::date_display")]
 	pub date: NaiveDate,
+	#[serde(default, deserialize_with = "de::time_option", skip_serializing)]
+	pub time: Option<NaiveTime>,
+	/// Where `time` is from, like `America/Denver`. Without one, the time is the same
+	/// wherever you are.
+	#[serde(default, skip_serializing)]
+	pub timezone: Option<Tz>,
+	/// Where the talk is given, like `Denver, CO`
+	pub venue: Option<String>,
+	/// What the talk is a part of, like a conference or a meetup
+	pub event: Option<String>,
+	pub event_url: Option<String>,
+	/// Orders entries that share a date and time, lowest first
+	#[serde(default)]
+	pub weight: i32,
 	#[serde(default, deserialize_with = "de::markdown_option")]
 	pub summary: Option<String>,
 	#[serde(default, deserialize_with = "de::comma_separated")]
 	pub tags: Vec<String>,
+	/// Old paths of the talk, which redirect to it
+	#[serde(default, deserialize_with = "de::comma_separated", skip_serializing)]
+	pub aliases: Vec<String>,
 	pub accent_color: Option<String>,
 	pub cover: Option<HashMap<String, String>>,
 	pub youtube: Option<String>,
 }
 
-impl<P> From<P> for Talk
-where
-	P: AsRef<Path>,
-{
-	fn from(path: P) -> Self {
-		let TextPage { metadata, content } = TextPage::<TalkMetadata>::from(&path);
+impl FromFile for Talk {
+	fn from_file(path: &Path) -> Result<Self, BuildError> {
+		let TextPage {
+			metadata,
+			content,
+			warnings,
+		} = TextPage::<TalkMetadata>::from_file(path)?;
 
-		let mut path = path.as_ref().to_owned();
-		path.set_extension("html");
+		let metadata = metadata.ok_or_else(|| {
+			BuildError::new("missing talk metadata")
+				.in_file(path)
+				.with_hint("talks start with a `---` block that sets a `title`, an `author`, and a `date`")
+		})?;
 
-		let metadata = metadata.expect(&format!("missing talk metadata in {}", path.display()));
+		let (slides, toc) = slide::split_slides(&content).map_err(|message| {
+			BuildError::new(message)
+				.in_file(path)
+				.with_hint("slide attributes look like `+++ {layout: title, class: center}`")
+		})?;
 
-		let content = content
-			.split("+++\n")
-			.map(md::markdown_to_html)
-			.collect::<Vec<_>>();
+		let mut path = path.to_owned();
+		path.set_extension("html");
 
-		Talk {
+		Ok(Talk {
+			canonical_url: config::canonicalize(&path),
 			path,
 			metadata,
-			content,
-		}
+			slide_count: slides.len() + 1,
+			slides,
+			toc,
+			upcoming: false,
+			warnings,
+		})
+	}
+}
+
+impl Talk {
+	pub fn sort_key(&self) -> SortKey<'_> {
+		SortKey::new(
+			Some(&self.metadata.date),
+			self.metadata.time.as_ref(),
+			self.metadata.weight,
+			&self.metadata.title,
+			&self.path,
+		)
+	}
+
+	pub fn canonicalize(&mut self) {
+		self.canonical_url = config::canonicalize(&self.path)
+	}
+
+	/// The html of every slide, one after another
+	pub fn content(&self) -> String {
+		self
+			.slides
+			.iter()
+			.map(|slide| slide.content.as_str())
+			.collect::<Vec<_>>()
+			.join("<hr />\n")
+	}
+
+	/// Like `content`, but with every link made absolute
+	pub fn absolute_content(&self) -> String {
+		self
+			.slides
+			.iter()
+			.map(|slide| md::render(&slide.source, false, Some(&self.canonical_url)).html)
+			.collect::<Vec<_>>()
+			.join("<hr />\n")
+	}
+
+	/// Where the presenter view of the talk ends up, like `talks/cool-video-games.notes.html`
+	pub fn notes_path(&self) -> PathBuf {
+		self.path.with_extension("notes.html")
+	}
+
+	/// Where the handout of the talk ends up, like `talks/cool-video-games.handout.html`
+	pub fn handout_path(&self) -> PathBuf {
+		self.path.with_extension("handout.html")
+	}
+
+	/// Renders every slide on one page, with the speaker notes underneath each of them,
+	/// for reading and printing rather than presenting
+	pub fn handout_as_html(&self) -> String {
+		let mut meta = Meta::talk(self);
+		meta.url = config::canonicalize(&self.handout_path()).to_string();
+		let mut data = serde_json::to_value(self).expect("failed to serialize talk");
+		data["blog"] = json!(&*BLOG);
+		data["meta"] = meta.to_html().into();
+		data["slides_url"] = format!("/{}", self.path.display()).into();
+		renderer()
+			.render_template(include_str!("./templates/talk_handout.html"), &data)
+			.expect("failed to render handlebars")
+	}
+
+	/// Renders the presenter view, which shows the current slide, the next slide, and
+	/// the speaker notes, and follows along with the slides in another window
+	pub fn notes_as_html(&self) -> String {
+		let mut data = serde_json::to_value(self).expect("failed to serialize talk");
+		data["blog"] = json!(&*BLOG);
+		data["slides_url"] = format!("/{}", self.path.display()).into();
+		renderer()
+			.render_template(include_str!("./templates/talk_notes.html"), &data)
+			.expect("failed to render handlebars")
 	}
 }
 
-handlebars_helper!(add: |a: i64, b: i64| a + b);
+/// Everything that the different renders of a talk have in common
+fn renderer() -> Handlebars<'static> {
+	let mut renderer = Handlebars::new();
+	renderer
+		.register_partial("toc", include_str!("./templates/toc.html"))
+		.expect("failed to register handlebars partial");
+	slide::register_layouts(&mut renderer);
+	renderer
+}
 
 impl AsHtml for Talk {
 	fn as_html(&self) -> String {
-		let mut renderer = Handlebars::new();
-		renderer.register_helper("add", Box::new(add));
-		renderer
-			.render_template(include_str!("./templates/talk.html"), self)
+		let mut data = serde_json::to_value(self).expect("failed to serialize talk");
+		data["blog"] = json!(&*BLOG);
+		data["meta"] = Meta::talk(self).to_html().into();
+		data["handout_url"] = format!("/{}", self.handout_path().display()).into();
+		renderer()
+			.render_template(include_str!("./templates/talk.html"), &data)
 			.expect("failed to render handlebars")
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::fs;
+
+	#[test]
+	fn renders_handout() {
+		let source = std::env::temp_dir().join(format!("handout-{}.md", std::process::id()));
+		fs::write(
+			&source,
+			"---\ntitle: Cool video games\nauthor: Kayla Washburn\ndate: 2023.7.15\n---\n\n# Why games\n<!-- Start with a story -->\n+++\n# Why games\nThey're fun\n+++\n## Which games\n- Celeste\n",
+		)
+		.unwrap();
+		let mut talk = Talk::from_file(&source).unwrap();
+		fs::remove_file(&source).unwrap();
+		talk.path = PathBuf::from("talks/cool-video-games.html");
+		talk.canonicalize();
+
+		let handout = talk.handout_as_html();
+		// Slides that repeat the heading before them don't get their own entry
+		assert!(handout.contains(r##"<li><a href="#slide-1">Why games</a></li>"##));
+		assert!(!handout.contains(r##"href="#slide-2""##));
+		assert!(handout.contains(r##"<li><a href="#slide-3">Which games</a></li>"##));
+		assert!(handout.contains(r#"<section class="handout-slide" id="slide-3">"#));
+		assert!(handout.contains("<li>Celeste</li>"));
+		assert_eq!(handout.matches(r#"<aside class="notes">"#).count(), 1);
+		assert!(handout.contains(r#"<aside class="notes"><p>Start with a story</p>"#));
+		assert!(handout.contains("4 slides"));
+		assert!(handout.contains(r#"<a href="/talks/cool-video-games.html">View the slides</a>"#));
+		assert!(handout.contains("https://mckayla.blog/talks/cool-video-games.handout.html"));
+	}
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+use std::time::SystemTime;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+type Snapshot = HashMap<PathBuf, SystemTime>;
+
+/// Watches the given directories by polling their modification times, and calls
+/// `on_change` with every file that was added, modified, or removed since the last
+/// poll. Never returns.
+pub fn watch<F>(directories: &[&Path], mut on_change: F) -> !
+where
+	F: FnMut(Vec<PathBuf>),
+{
+	let mut previous = snapshot(directories);
+
+	loop {
+		thread::sleep(POLL_INTERVAL);
+
+		let current = snapshot(directories);
+		let mut changes = current
+			.iter()
+			.filter(|(path, modified)| previous.get(*path) != Some(modified))
+			.map(|(path, _)| path.clone())
+			.chain(
+				previous
+					.keys()
+					.filter(|path| !current.contains_key(*path))
+					.cloned(),
+			)
+			.collect::<Vec<_>>();
+
+		if !changes.is_empty() {
+			changes.sort();
+			on_change(changes);
+		}
+
+		previous = current;
+	}
+}
+
+fn snapshot(directories: &[&Path]) -> Snapshot {
+	let mut snapshot = Snapshot::new();
+	for directory in directories {
+		collect(directory, &mut snapshot);
+	}
+	snapshot
+}
+
+fn collect(directory: &Path, snapshot: &mut Snapshot) {
+	let Ok(entries) = fs::read_dir(directory) else {
+		return;
+	};
+
+	for entry in entries.flatten() {
+		let path = entry.path();
+		let Ok(metadata) = entry.metadata() else {
+			continue;
+		};
+
+		if metadata.is_dir() {
+			collect(&path, snapshot);
+		} else if let Ok(modified) = metadata.modified() {
+			snapshot.insert(path, modified);
+		}
+	}
+}